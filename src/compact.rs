@@ -0,0 +1,149 @@
+use std::io::{self, Read, Write};
+
+use crate::{ParseError, PathSegment};
+
+pub const MAGIC: &[u8; 4] = b"D3WB";
+const VERSION: u8 = 1;
+
+fn write_varint(out: &mut impl Write, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+// the magic D3WB and a version byte, then per wire its move count and per move a direction byte
+// (R, U, L or D) and the distance; counts and distances are LEB128 varints, starts and weights
+// are not kept
+pub fn write(out: &mut impl Write, wires: [&[PathSegment]; 2]) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    for wire in wires.iter() {
+        write_varint(out, wire.len() as u64)?;
+        for m in wire.iter() {
+            out.write_all(&[m.direction as u8])?;
+            write_varint(out, m.distance as u64)?;
+        }
+    }
+    out.flush()
+}
+
+// counts the bytes read so errors can say where they are
+struct Reader<R> {
+    inner: R,
+    offset: usize,
+}
+
+impl<R: Read> Reader<R> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError { offset: self.offset, message: message.to_string() }
+    }
+
+    fn byte(&mut self) -> Result<u8, ParseError> {
+        let mut byte = [0u8];
+        self.inner.read_exact(&mut byte).map_err(|_| self.error("unexpected end of file"))?;
+        self.offset += 1;
+        Ok(byte[0])
+    }
+
+    fn varint(&mut self, max: u64) -> Result<u64, ParseError> {
+        let start = self.offset;
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                if value > max {
+                    return Err(ParseError { offset: start, message: format!("{} is out of range", value) });
+                }
+                return Ok(value);
+            }
+        }
+        Err(ParseError { offset: start, message: "varint too long".to_string() })
+    }
+}
+
+// the layout write produces; any command reads it in place of text, told apart by the magic or
+// required with --format bin
+pub fn read(input: impl Read) -> Result<(Vec<PathSegment>, Vec<PathSegment>), ParseError> {
+    let mut reader = Reader { inner: input, offset: 0 };
+    // errors point at the byte that is wrong, the one just read
+    for expected in MAGIC.iter() {
+        if reader.byte()? != *expected {
+            return Err(ParseError { offset: reader.offset - 1, message: "not a compact wire file".to_string() });
+        }
+    }
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(ParseError { offset: reader.offset - 1, message: format!("format version {}, expected {}", version, VERSION) });
+    }
+    let mut wires: Vec<Vec<PathSegment>> = vec!();
+    for _ in 0..2 {
+        // no capacity from the count, a corrupt one could ask for anything
+        let count = reader.varint(u32::MAX as u64)?;
+        let mut wire: Vec<PathSegment> = vec!();
+        for _ in 0..count {
+            let direction = reader.byte()? as char;
+            if !"RULD".contains(direction) {
+                return Err(ParseError { offset: reader.offset - 1, message: format!("invalid direction '{}'", direction.escape_default()) });
+            }
+            let distance = reader.varint(i32::MAX as u64)? as i32;
+            wire.push(PathSegment { direction, distance });
+        }
+        wires.push(wire);
+    }
+    let wire2 = wires.pop().unwrap();
+    let wire1 = wires.pop().unwrap();
+    Ok((wire1, wire2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{moves, EXAMPLES};
+
+    fn written(wires: [&[PathSegment]; 2]) -> Vec<u8> {
+        let mut out: Vec<u8> = vec!();
+        write(&mut out, wires).unwrap();
+        out
+    }
+
+    fn error(bytes: &[u8]) -> (usize, String) {
+        let e = read(bytes).unwrap_err();
+        (e.offset, e.message)
+    }
+
+    #[test]
+    fn round_trips_the_examples() {
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            let (wire1, wire2) = (moves(line1), moves(line2));
+            assert_eq!(read(&written([&wire1, &wire2])[..]).unwrap(), (wire1, wire2));
+        }
+        let empty: Vec<PathSegment> = vec!();
+        assert_eq!(read(&written([&empty, &empty])[..]).unwrap(), (vec!(), vec!()));
+    }
+
+    #[test]
+    fn layout_of_a_small_input() {
+        // 300 takes two varint bytes, 0xac 0x02
+        assert_eq!(written([&moves("R8,U300"), &moves("L1")]),
+                   vec!(b'D', b'3', b'W', b'B', 1, 2, b'R', 8, b'U', 0xac, 0x02, 1, b'L', 1));
+    }
+
+    #[test]
+    fn bad_files_say_where() {
+        let bytes = written([&moves("R8,U300"), &moves("L1")]);
+        // cut anywhere short of the end
+        for len in 0..bytes.len() {
+            assert_eq!(error(&bytes[..len]), (len, "unexpected end of file".to_string()), "{} bytes", len);
+        }
+        assert_eq!(error(b"D3WA\x01"), (3, "not a compact wire file".to_string()));
+        assert_eq!(error(b"D3WB\x02"), (4, "format version 2, expected 1".to_string()));
+        assert_eq!(error(b"D3WB\x01\x01X\x01\x00"), (6, "invalid direction 'X'".to_string()));
+        assert_eq!(error(b"D3WB\x01\x01R\xff\xff\xff\xff\x0f\x00"), (7, "4294967295 is out of range".to_string()));
+    }
+}
@@ -1,22 +1,113 @@
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
-use std::io::{Lines, BufRead, BufReader, Result};
+use std::io::{BufRead, BufReader, IsTerminal, Write};
 use std::cmp::{min, max};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
+use point::Point;
+use session::{Edit, Session};
+use units::{Distance, Steps};
+use wire::{opposite, PreparedWire, Wire};
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+// debug tracing of the solver, enabled by --verbose
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if VERBOSE.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod compact;
+mod point;
+mod random;
+mod render;
+mod route;
+mod session;
+mod stats;
+mod units;
+mod wire;
+
+// how two segments meet
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum CrossingKind {
+    // through the middle of both
+    Cross,
+    // at an end of both, e.g. two corners
+    CornerTouch,
+    // at an end of one, the middle of the other
+    EndpointTouch,
+    // along the same line
+    Overlap,
+}
+
+impl fmt::Display for CrossingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CrossingKind::Cross => "cross",
+            CrossingKind::CornerTouch => "corner",
+            CrossingKind::EndpointTouch => "endpoint",
+            CrossingKind::Overlap => "overlap",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// a crossing with where each wire is when it gets there; per wire index 0 is wire 1, indices into
+// its segments and moves are 0-based
 #[derive(Copy, Debug, Clone)]
-struct Point {
-    x: i32,
-    y: i32,
+struct Intersection {
+    point: Point,
+    distance: Distance,
+    steps: [Steps; 2],
+    segment_index: [usize; 2],
+    move_index: [usize; 2],
+    kind: CrossingKind,
+}
+
+impl Intersection {
+    // p on segment a, at index i of wire 1, and on segment b, at index j of wire 2; moves are what
+    // each wire was built from and distance is measured from origin
+    fn new(p: Point, (i, a): (usize, &Segment), (j, b): (usize, &Segment), kind: CrossingKind, moves: [&[PathSegment]; 2], origin: Point) -> Intersection {
+        let move_of = |moves: &[PathSegment], s: &Segment| move_at(moves, s.steps + distance(p, s.start), p == s.start);
+        Intersection {
+            point: p,
+            distance: Distance(distance(p, origin)),
+            steps: [Steps(cost_for_segment(p, a)), Steps(cost_for_segment(p, b))],
+            segment_index: [i, j],
+            move_index: [move_of(moves[0], a), move_of(moves[1], b)],
+            kind,
+        }
+    }
+
+    // the part 2 cost
+    fn cost(&self) -> Steps {
+        Steps(self.steps[0].0 + self.steps[1].0)
+    }
 }
 
 #[derive(Copy, Debug, Clone)]
 struct PointWithCost {
     point: Point,
-    cost: i32,
+    cost: Steps,
+    kind: CrossingKind,
 }
 
-#[derive(Copy, Debug, Clone)]
+// axis aligned, min and max corners included
+#[derive(Copy, Debug, Clone, PartialEq)]
+struct Rect {
+    min: Point,
+    max: Point,
+}
+
+#[derive(Copy, Debug, Clone, PartialEq)]
 struct PathSegment {
     direction: char,
     distance: i32,
@@ -26,13 +117,35 @@ struct PathSegment {
 struct Segment {
     end1: Point,
     end2: Point,
+    // steps walked before the segment, from start over length steps; kept apart from the
+    // normalized ends so step counting never depends on which way they were swapped
     steps: i32,
+    start: Point,
+    length: i32,
+    // end1 and end2 were swapped by normalize; geometry only
     mirrored: bool,
+    // cost of each step on this wire, 1 unless the input gives one, see split_weight
+    weight: i32,
 }
 
-impl fmt::Display for Point {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({},{})", self.x, self.y)
+impl Rect {
+    // the smallest rect holding both
+    fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: Point::new(min(self.min.x(), other.min.x()), min(self.min.y(), other.min.y())),
+            max: Point::new(max(self.max.x(), other.max.x()), max(self.max.y(), other.max.y())),
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        between(p.x(), self.min.x(), self.max.x()) && between(p.y(), self.min.y(), self.max.y())
+    }
+}
+
+impl Segment {
+    // where the wire leaves the segment, the end other than start
+    fn end(&self) -> Point {
+        if self.mirrored { self.end1 } else { self.end2 }
     }
 }
 
@@ -49,38 +162,318 @@ impl fmt::Display for PathSegment {
     }
 }
 
-fn toPathSegment(c: &str) -> PathSegment {
-    let direction = c.chars().next().unwrap();
-    let distance: i32 = c.trim()[1..].parse().unwrap();
-    return PathSegment { direction, distance };
+#[derive(Debug, Clone)]
+struct ParseError {
+    offset: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "parse error at offset {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+enum Error {
+    Io { path: String, source: std::io::Error },
+    // line is 1-based, the offset within it is in source
+    Parse { path: String, line: usize, source: ParseError },
+    // an input must hold exactly two wires
+    WireCount { path: String, found: usize },
+    // a compact binary input that does not decode, the offset in bytes
+    Compact { path: String, source: ParseError },
+    // wire is 1-based, see --max-segments
+    TooManySegments { path: String, wire: usize, found: usize, limit: usize },
+    // wire and move are 1-based, cell is the first one of the move inside the obstacle
+    EntersObstacle { path: String, wire: usize, index: usize, cell: Point, obstacle: Rect },
+    // a command line option with a value it cannot take
+    Usage { message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path, source),
+            Error::Parse { path, line, source } => write!(f, "{}:{}: {}", path, line, source),
+            Error::Compact { path, source } => write!(f, "{}: {}", path, source),
+            Error::WireCount { path, found } => write!(f, "{}: expected 2 wires, found {}", path, found),
+            Error::TooManySegments { path, wire, found, limit } =>
+                write!(f, "{}: wire {} has {} segments, more than the limit of {}", path, wire, found, limit),
+            Error::EntersObstacle { path, wire, index, cell, obstacle } =>
+                write!(f, "{}: wire {} move {} enters the obstacle {} to {} at {}", path, wire, index, obstacle.min, obstacle.max, cell),
+            Error::Usage { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Parse { source, .. } | Error::Compact { source, .. } => Some(source),
+            Error::WireCount { .. } | Error::TooManySegments { .. } | Error::EntersObstacle { .. } | Error::Usage { .. } => None,
+        }
+    }
+}
+
+// raw "x1,y1,x2,y2" geometry, normalized, with no steps walked before it
+impl TryFrom<&str> for Segment {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> std::result::Result<Segment, ParseError> {
+        let mut coords: Vec<i32> = vec!();
+        let mut offset = 0;
+        for field in s.split(',') {
+            let start = offset + (field.len() - field.trim_start().len());
+            let value = field.trim().parse().map_err(|_| ParseError { offset: start, message: format!("invalid coordinate '{}'", field.trim()) })?;
+            coords.push(value);
+            offset += field.len() + 1;
+        }
+        if coords.len() != 4 {
+            return Err(ParseError { offset: 0, message: format!("expected 4 coordinates, found {}", coords.len()) });
+        }
+        let end1 = Point::new(coords[0], coords[1]);
+        let end2 = Point::new(coords[2], coords[3]);
+        if end1.x() != end2.x() && end1.y() != end2.y() {
+            return Err(ParseError { offset: 0, message: format!("segment {}-{} is not axis aligned", end1, end2) });
+        }
+        Ok(normalize(vec![Segment { end1, end2, steps: 0, start: end1, length: distance(end1, end2), mirrored: false, weight: 1 }])[0])
+    }
+}
+
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum Syntax {
+    // AoC letters: R8,U5,L5,D3
+    Aoc,
+    // signed axes: x+8,y+5,x-5,y-3
+    Xy,
+}
+
+const XY_DIRECTIONS: [(&str, char); 4] = [("x+", 'R'), ("x-", 'L'), ("y+", 'U'), ("y-", 'D')];
+
+impl Syntax {
+    // splits a move token into its direction prefix and the distance text
+    fn split(self, token: &str) -> (&str, &str) {
+        let prefix_len = match self {
+            Syntax::Aoc => token.chars().next().map_or(0, |c| c.len_utf8()),
+            Syntax::Xy => token.char_indices().nth(2).map_or(token.len(), |(i, _)| i),
+        };
+        token.split_at(prefix_len)
+    }
+
+    // a move as it is written
+    fn token(self, m: &PathSegment) -> String {
+        match self {
+            Syntax::Aoc => format!("{}{}", m.direction, m.distance),
+            Syntax::Xy => {
+                let prefix = XY_DIRECTIONS.iter().find(|(_, d)| *d == m.direction).map_or("?", |(p, _)| *p);
+                format!("{}{}", prefix, m.distance)
+            }
+        }
+    }
+
+    fn direction(self, prefix: &str) -> Option<char> {
+        match self {
+            Syntax::Aoc => prefix.chars().next().filter(|c| "UDLR".contains(*c)),
+            Syntax::Xy => XY_DIRECTIONS.iter().find(|(p, _)| *p == prefix).map(|(_, d)| *d),
+        }
+    }
+}
+
+// how inputs are read
+#[derive(Debug, Clone, Copy)]
+struct ParseOptions {
+    syntax: Syntax,
+    // a negative distance turns a move around, R-5 being L5; rejected unless --signed-distance
+    signed_distance: bool,
+    // every input is the compact binary form, set by --format bin; otherwise the form is told by
+    // the magic at the start of the file
+    binary: bool,
+}
+
+impl ParseOptions {
+    fn new(syntax: Syntax) -> ParseOptions {
+        ParseOptions { syntax, signed_distance: false, binary: false }
+    }
+}
+
+fn parse_move(token: &str, offset: usize, options: ParseOptions) -> std::result::Result<PathSegment, ParseError> {
+    let syntax = options.syntax;
+    let error = |message: String| ParseError { offset, message };
+    if token.is_empty() {
+        return Err(error("empty move".to_string()));
+    }
+    let (prefix, rest) = syntax.split(token);
+    let direction = syntax.direction(prefix).ok_or_else(|| error(format!("invalid direction '{}'", prefix)))?;
+    let distance: i32 = rest.parse().map_err(|_| error(format!("invalid distance '{}'", rest)))?;
+    if distance >= 0 {
+        return Ok(PathSegment { direction, distance });
+    }
+    if !options.signed_distance {
+        return Err(error(format!("negative distance '{}'", rest)));
+    }
+    let distance = distance.checked_neg().ok_or_else(|| error(format!("invalid distance '{}'", rest)))?;
+    Ok(PathSegment { direction: opposite(direction), distance })
+}
+
+// an optional "xN:" before a wire's moves makes each of its steps cost N; returns the weight, the
+// rest of the line and where that rest starts
+fn split_weight(line: &str) -> std::result::Result<(i32, &str, usize), ParseError> {
+    let weighted = line.trim_start().strip_prefix('x').and_then(|rest| rest.split_once(':'))
+        .filter(|(w, _)| !w.is_empty() && w.chars().all(|c| c.is_ascii_digit()));
+    match weighted {
+        Some((w, rest)) => {
+            let offset = line.len() - rest.len();
+            let weight: i32 = w.parse().ok().filter(|w| *w > 0)
+                .ok_or(ParseError { offset: line.len() - line.trim_start().len() + 1, message: format!("invalid weight {}", w) })?;
+            Ok((weight, rest, offset))
+        }
+        None => Ok((1, line, 0)),
+    }
+}
+
+// an optional "(X,Y):" before a wire's weight and moves starts it at X,Y instead of the shared
+// start; returns the start, the rest of the line and where that rest starts
+fn split_start(line: &str) -> std::result::Result<(Option<Point>, &str, usize), ParseError> {
+    let trimmed = line.trim_start();
+    let at = line.len() - trimmed.len();
+    if !trimmed.starts_with('(') {
+        return Ok((None, line, 0));
+    }
+    let invalid = |message: &str| ParseError { offset: at, message: message.to_string() };
+    let (point, rest) = trimmed[1..].split_once(')').ok_or_else(|| invalid("unclosed start"))?;
+    let rest = rest.trim_start().strip_prefix(':').ok_or_else(|| invalid("expected ':' after the start"))?;
+    let start = parse_point(point).ok_or_else(|| invalid(&format!("invalid start ({})", point)))?;
+    Ok((Some(start), rest, line.len() - rest.len()))
+}
+
+fn parse_path(line: &str, options: ParseOptions) -> std::result::Result<Vec<PathSegment>, ParseError> {
+    let mut path: Vec<PathSegment> = vec!();
+    let mut offset = 0;
+    for token in line.split(',') {
+        let start = offset + (token.len() - token.trim_start().len());
+        path.push(parse_move(token.trim(), start, options)?);
+        offset += token.len() + 1;
+    }
+    Ok(path)
 }
 
-fn path_to_segments(path: Vec<PathSegment>) -> Vec<Segment> {
+fn path_to_segments(path: Vec<PathSegment>, origin: Point) -> Vec<Segment> {
     let mut segments: Vec<Segment> = vec!();
-    let mut curr = Point { x: 0, y: 0 };
+    let mut curr = origin;
     let mut steps = 0;
     for step in path {
         let next = match step.direction {
-            'U' => Point { x: curr.x, y: curr.y + step.distance },
-            'D' => Point { x: curr.x, y: curr.y - step.distance },
-            'L' => Point { x: curr.x - step.distance, y: curr.y },
-            'R' => Point { x: curr.x + step.distance, y: curr.y },
+            'U' => Point::new(curr.x(), curr.y() + step.distance),
+            'D' => Point::new(curr.x(), curr.y() - step.distance),
+            'L' => Point::new(curr.x() - step.distance, curr.y()),
+            'R' => Point::new(curr.x() + step.distance, curr.y()),
             _ => panic!("unknown direction")
         };
-        let segment = Segment { end1: curr, end2: next, steps, mirrored: false };
+        if step.distance == 0 {
+            // end1 == end2 would pass as a vertical segment and match the other wire spuriously
+            verbose!("{} => skipped, zero length", step);
+            continue;
+        }
+        let segment = Segment { end1: curr, end2: next, steps, start: curr, length: step.distance, mirrored: false, weight: 1 };
         steps += step.distance;
-        println!("{} => {} ", step, segment);
+        verbose!("{} => {} ", step, segment);
         segments.push(segment);
         curr = next;
     }
-    return segments;
+    segments
+}
+
+// one step of a move in direction
+fn unit_step(direction: char) -> (i32, i32) {
+    match direction {
+        'U' => (0, 1),
+        'D' => (0, -1),
+        'L' => (-1, 0),
+        'R' => (1, 0),
+        _ => panic!("unknown direction")
+    }
+}
+
+// where a move from at ends, wrapped onto the torus if there is one
+fn move_end(at: Point, step: &PathSegment, wrap: Option<(i32, i32)>) -> Point {
+    let (dx, dy) = unit_step(step.direction);
+    let end = Point::new(at.x() + dx * step.distance, at.y() + dy * step.distance);
+    match wrap {
+        Some((width, height)) => Point::new(end.x().rem_euclid(width), end.y().rem_euclid(height)),
+        None => end,
+    }
+}
+
+// path_to_segments on a torus of width by height cells, see --wrap: a move running off one edge
+// comes back in at the opposite one, the step between them counted like any other, so a move
+// becomes several pieces inside 0..width by 0..height. As in path_to_segments there are no pieces
+// of length 0, so a cell only reached by the step over an edge, where the wire then ends or goes
+// straight back over, is in no piece
+fn path_to_wrapped_segments(path: Vec<PathSegment>, origin: Point, (width, height): (i32, i32)) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = vec!();
+    let mut curr = Point::new(origin.x().rem_euclid(width), origin.y().rem_euclid(height));
+    let mut steps = 0;
+    for step in path {
+        let (dx, dy) = unit_step(step.direction);
+        let mut left = step.distance;
+        let piece = |from: Point, to: Point, steps: i32, length: i32| {
+            let segment = Segment { end1: from, end2: to, steps, start: from, length, mirrored: false, weight: 1 };
+            verbose!("{} => {} ", step, segment);
+            segment
+        };
+        while left > 0 {
+            // cells before the edge in this direction
+            let room = match (dx, dy) {
+                (1, _) => width - 1 - curr.x(),
+                (-1, _) => curr.x(),
+                (_, 1) => height - 1 - curr.y(),
+                _ => curr.y(),
+            };
+            let length = min(room, left);
+            let next = Point::new(curr.x() + dx * length, curr.y() + dy * length);
+            if length > 0 {
+                segments.push(piece(curr, next, steps, length));
+            }
+            steps += length;
+            left -= length;
+            curr = next;
+            if left > 0 {
+                // over the edge
+                curr = Point::new((curr.x() + dx).rem_euclid(width), (curr.y() + dy).rem_euclid(height));
+                steps += 1;
+                left -= 1;
+            }
+        }
+    }
+    normalize(segments)
+}
+
+fn coalesce(segments: &[Segment]) -> Vec<Segment> {
+    let mut coalesced: Vec<Segment> = vec!();
+    for segment in segments {
+        if let Some(last) = coalesced.last_mut() {
+            let same_direction = (last.end2.x() - last.end1.x()).signum() == (segment.end2.x() - segment.end1.x()).signum()
+                && (last.end2.y() - last.end1.y()).signum() == (segment.end2.y() - segment.end1.y()).signum();
+            if same_direction && last.end2.x() == segment.end1.x() && last.end2.y() == segment.end1.y() {
+                last.end2 = segment.end2;
+                last.length += segment.length;
+                continue;
+            }
+        }
+        coalesced.push(*segment);
+    }
+    coalesced
 }
 
 fn normalize(segments: Vec<Segment>) -> Vec<Segment> {
     let mut normalized: Vec<Segment> = vec!();
     for segment in segments {
-        if segment.end1.x > segment.end2.x || segment.end1.y > segment.end2.y {
-            normalized.push(Segment { end1: segment.end2, end2: segment.end1, steps: segment.steps, mirrored: true });
+        if segment.end1.x() > segment.end2.x() || segment.end1.y() > segment.end2.y() {
+            normalized.push(Segment { end1: segment.end2, end2: segment.end1, mirrored: true, ..segment });
         } else {
             normalized.push(segment);
         }
@@ -88,12 +481,64 @@ fn normalize(segments: Vec<Segment>) -> Vec<Segment> {
     normalized
 }
 
+// the part of the wire walked in its first `steps` steps, cutting mid-segment if needed
+fn prefix(segments: &[Segment], steps: i32) -> Vec<Segment> {
+    let mut cut: Vec<Segment> = vec!();
+    for s in segments {
+        if s.steps >= steps {
+            break;
+        }
+        if s.steps + s.length <= steps {
+            cut.push(*s);
+            continue;
+        }
+        let (start, end) = (s.start, s.end());
+        let walked = steps - s.steps;
+        let stop = Point::new(start.x() + (end.x() - start.x()).signum() * walked, start.y() + (end.y() - start.y()).signum() * walked);
+        cut.extend(normalize(vec![Segment { end1: start, end2: stop, length: walked, mirrored: false, ..*s }]));
+        break;
+    }
+    cut
+}
+
+// the corners of the wire in walking order, from its start to its end, each with the steps walked
+// to reach it; kind is always CornerTouch
+fn waypoints(segments: &[Segment]) -> Vec<PointWithCost> {
+    let mut points: Vec<PointWithCost> = vec!();
+    for s in segments {
+        if points.is_empty() {
+            points.push(PointWithCost { point: s.start, cost: Steps(s.steps), kind: CrossingKind::CornerTouch });
+        }
+        points.push(PointWithCost { point: s.end(), cost: Steps(s.steps + s.length), kind: CrossingKind::CornerTouch });
+    }
+    points
+}
+
+// as waypoints, without the steps
+fn vertices(segments: &[Segment]) -> Vec<Point> {
+    waypoints(segments).iter().map(|w| w.point).collect()
+}
+
+// the unit step of each leg of the wire, in walking order
+fn headings(segments: &[Segment]) -> Vec<(i32, i32)> {
+    vertices(segments).windows(2).map(|w| ((w[1].x() - w[0].x()).signum(), (w[1].y() - w[0].y()).signum())).collect()
+}
+
+// how often the wire switches between horizontal and vertical
+fn turn_count(segments: &[Segment]) -> usize {
+    headings(segments).windows(2).filter(|w| (w[0].0 == 0) != (w[1].0 == 0)).count()
+}
+
+// whether the wire ever doubles straight back on itself, usually a typo in the input
+fn reverses(segments: &[Segment]) -> bool {
+    headings(segments).windows(2).any(|w| w[0].0 == -w[1].0 && w[0].1 == -w[1].1)
+}
+
 fn split_on_direction(segments: Vec<Segment>) -> (Vec<Segment>, Vec<Segment>) {
     let mut horizontals: Vec<Segment> = vec!();
     let mut verticals: Vec<Segment> = vec!();
-    let mut intersects: Vec<Point> = vec!();
     for segment in segments {
-        if segment.end1.x == segment.end2.x {
+        if segment.end1.x() == segment.end2.x() {
             verticals.push(segment);
         } else {
             horizontals.push(segment);
@@ -103,146 +548,2934 @@ fn split_on_direction(segments: Vec<Segment>) -> (Vec<Segment>, Vec<Segment>) {
 }
 
 fn cost_for_segment(p: Point, s: &Segment) -> i32 {
-    if s.end1.x != s.end2.x {
-        // horizontal
-        if s.mirrored {
-            return s.steps + s.end2.x - p.x;
-        } else {
-            return s.steps + p.x - s.end1.x;
-        }
-    } else {
-        // vertical
-        if s.mirrored {
-            return s.steps + s.end2.y - p.y;
-        } else {
-            return s.steps + p.y - s.end1.y;
+    s.weight * (s.steps + distance(p, s.start))
+}
+
+// steps the wire needs to first reach p
+fn steps_to(segments: &[Segment], p: Point) -> Option<i32> {
+    segments.iter().filter(|s| contains(s, p)).map(|s| cost_for_segment(p, s)).min()
+}
+
+// the slow reference for steps_to: walks the wire one lattice step at a time until it reaches target.
+// Only the tests compare the two
+#[cfg(test)]
+fn steps_along(segments: &[Segment], target: Point) -> Option<i32> {
+    let corners = vertices(segments);
+    let mut curr = *corners.first()?;
+    let mut steps = 0;
+    if curr == target {
+        return Some(0);
+    }
+    for next in corners.iter().skip(1) {
+        while curr != *next {
+            curr = Point::new(curr.x() + (next.x() - curr.x()).signum(), curr.y() + (next.y() - curr.y()).signum());
+            steps += 1;
+            if curr == target {
+                return Some(steps);
+            }
         }
     }
+    None
 }
 
+// segment1 is on the wire being walked (path1), segment2 on the other one. The sum does not depend
+// on the order, but the intersect functions keep it so the roles are never mixed up
 fn cost(p: Point, segment1: &Segment, segment2: &Segment) -> i32 {
-    return cost_for_segment(p, segment1) + cost_for_segment(p, segment2);
-}
-
-fn intersects_horizontal(segment: Segment, horizontals: &Vec<Segment>, verticals: &Vec<Segment>) -> Vec<PointWithCost> {
-    let mut intersects: Vec<PointWithCost> = vec!();
-    for other in horizontals {
-        if segment.end1.y == other.end1.y {
-            let leftmost = max(segment.end1.x, other.end1.x);
-            let rightmost = min(segment.end2.x, other.end2.x);
-            let point1 = Point { x: leftmost, y: segment.end1.y };
-            let point2 = Point { x: rightmost, y: segment.end1.y };
-            let cost1 = cost(point1, other, &segment);
-            let cost2 = cost(point2, other, &segment);
-            intersects.push(PointWithCost { point: point1, cost: cost1 });
-            intersects.push(PointWithCost { point: point2, cost: cost2 });
-        }
-    }
-    for other in verticals {
-        if between(other.end1.x, segment.end1.x, segment.end2.x) {
-            if between(segment.end1.y, other.end1.y, other.end2.y) {
-                let point = Point { x: other.end1.x, y: segment.end1.y };
-                let cost1 = cost(point, other, &segment);
-                intersects.push(PointWithCost { point, cost: cost1 });
-            }
-        }
-    }
-    intersects
-}
-
-fn intersects_vertical(segment: Segment, horizontals: &Vec<Segment>, verticals: &Vec<Segment>) -> Vec<PointWithCost> {
-    let mut intersects: Vec<PointWithCost> = vec!();
-    for other in verticals {
-        if segment.end1.x == other.end1.x {
-            let bottom = max(segment.end1.y, other.end1.y);
-            let top = min(segment.end2.y, other.end2.y);
-            let point1 = Point { x: segment.end1.x, y: bottom };
-            let point2 = Point { x: segment.end1.x, y: top };
-            println!("intersect: {},{} -> {} {} ", segment, other, point1, point2);
-            let cost1 = cost(point1, &segment, other);
-            let cost2 = cost(point2, &segment, other);
-            intersects.push(PointWithCost { point: point1, cost: cost1 });
-            intersects.push(PointWithCost { point: point2, cost: cost2 });
-        }
-    }
-    for other in horizontals {
-        if between(other.end1.y, segment.end1.y, segment.end2.y) {
-            if between(segment.end1.x, other.end1.x, other.end2.x) {
-                let point = Point { x: segment.end1.x, y: other.end1.y };
+    cost_for_segment(p, segment1) + cost_for_segment(p, segment2)
+}
+
+fn is_endpoint(p: Point, s: &Segment) -> bool {
+    (p.x() == s.end1.x() && p.y() == s.end1.y()) || (p.x() == s.end2.x() && p.y() == s.end2.y())
+}
+
+// perpendicular segments cross transversally unless the point is an end of either one
+fn crossing_kind(p: Point, segment1: &Segment, segment2: &Segment) -> CrossingKind {
+    match (is_endpoint(p, segment1), is_endpoint(p, segment2)) {
+        (true, true) => CrossingKind::CornerTouch,
+        (false, false) => CrossingKind::Cross,
+        _ => CrossingKind::EndpointTouch,
+    }
+}
+
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    fn of(s: &Segment) -> Axis {
+        if s.end1.x() == s.end2.x() { Axis::Vertical } else { Axis::Horizontal }
+    }
+
+    // the coordinate that varies along a segment on this axis
+    fn along(self, p: Point) -> i32 {
+        match self {
+            Axis::Horizontal => p.x(),
+            Axis::Vertical => p.y(),
+        }
+    }
+
+    // the coordinate that is fixed for a segment on this axis
+    fn across(self, p: Point) -> i32 {
+        match self {
+            Axis::Horizontal => p.y(),
+            Axis::Vertical => p.x(),
+        }
+    }
+
+    fn point(self, along: i32, across: i32) -> Point {
+        match self {
+            Axis::Horizontal => Point::new(along, across),
+            Axis::Vertical => Point::new(across, along),
+        }
+    }
+}
+
+// which points of a stretch where the wires overlap are reported
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum OverlapPoints {
+    // its two ends, as the puzzle needs
+    Ends,
+    // every lattice point along it
+    Every,
+}
+
+// segment runs along axis, parallel are the other wire's segments on the same axis and
+// perpendicular the ones across it
+fn intersects_along(axis: Axis, segment: Segment, parallel: &[Segment], perpendicular: &[Segment], overlap: OverlapPoints, intersects: &mut Vec<PointWithCost>) {
+    let (along, across) = (|p| axis.along(p), |p| axis.across(p));
+    for other in parallel {
+        if across(segment.end1) == across(other.end1) {
+            let low = max(along(segment.end1), along(other.end1));
+            let high = min(along(segment.end2), along(other.end2));
+            if low > high {
+                // on the same line but apart
+                continue;
+            }
+            let point1 = axis.point(low, across(segment.end1));
+            let point2 = axis.point(high, across(segment.end1));
+            verbose!("intersect: {},{} -> {} {} ", segment, other, point1, point2);
+            let points: Vec<Point> = match overlap {
+                OverlapPoints::Ends => vec![point1, point2],
+                OverlapPoints::Every => (low..=high).map(|a| axis.point(a, across(segment.end1))).collect(),
+            };
+            for point in points {
                 let cost1 = cost(point, &segment, other);
-                intersects.push(PointWithCost { point, cost: cost1 });
+                intersects.push(PointWithCost { point, cost: Steps(cost1), kind: CrossingKind::Overlap });
             }
         }
     }
-    intersects
+    for other in perpendicular {
+        // other runs across axis, so its along coordinate is fixed
+        if between(along(other.end1), along(segment.end1), along(segment.end2))
+            && between(across(segment.end1), across(other.end1), across(other.end2)) {
+            let point = axis.point(along(other.end1), across(segment.end1));
+            let cost1 = cost(point, &segment, other);
+            let kind = crossing_kind(point, &segment, other);
+            intersects.push(PointWithCost { point, cost: Steps(cost1), kind });
+        }
+    }
 }
 
-fn intersects(segment: Segment, horizontals: &Vec<Segment>, verticals: &Vec<Segment>) -> Vec<PointWithCost> {
-    if segment.end1.x == segment.end2.x {
-        println!("Vertical for : {}", segment);
-        return intersects_vertical(segment, &horizontals, &verticals);
-    } else {
-        println!("Horizontal for : {}", segment);
-        return intersects_horizontal(segment, &horizontals, &verticals);
+// appends the intersections of segment with the other wire to intersects
+fn intersects(segment: Segment, horizontals: &[Segment], verticals: &[Segment], intersects: &mut Vec<PointWithCost>) {
+    intersects_with(segment, horizontals, verticals, OverlapPoints::Ends, intersects);
+}
+
+fn intersects_with(segment: Segment, horizontals: &[Segment], verticals: &[Segment], overlap: OverlapPoints, intersects: &mut Vec<PointWithCost>) {
+    match Axis::of(&segment) {
+        Axis::Vertical => {
+            verbose!("Vertical for : {}", segment);
+            intersects_along(Axis::Vertical, segment, verticals, horizontals, overlap, intersects);
+        }
+        Axis::Horizontal => {
+            verbose!("Horizontal for : {}", segment);
+            intersects_along(Axis::Horizontal, segment, horizontals, verticals, overlap, intersects);
+        }
     }
 }
 
 fn between(i: i32, low: i32, high: i32) -> bool {
     if i < low { return false; }
     if i > high { return false; }
-    return true;
+    true
+}
+
+// segments are axis aligned, so p is on s when it is inside their bounding box
+fn contains(s: &Segment, p: Point) -> bool {
+    between(p.x(), min(s.end1.x(), s.end2.x()), max(s.end1.x(), s.end2.x()))
+        && between(p.y(), min(s.end1.y(), s.end2.y()), max(s.end1.y(), s.end2.y()))
+}
+
+// the part of s inside rect, with steps counted up to where the piece starts
+fn clip_segment(s: &Segment, rect: Rect) -> Option<Segment> {
+    let (x1, x2) = (max(min(s.end1.x(), s.end2.x()), rect.min.x()), min(max(s.end1.x(), s.end2.x()), rect.max.x()));
+    let (y1, y2) = (max(min(s.end1.y(), s.end2.y()), rect.min.y()), min(max(s.end1.y(), s.end2.y()), rect.max.y()));
+    if x1 > x2 || y1 > y2 {
+        return None;
+    }
+    let clamp = |p: Point| Point::new(p.x().max(x1).min(x2), p.y().max(y1).min(y2));
+    let (end1, end2) = (clamp(s.end1), clamp(s.end2));
+    let steps = s.steps + distance(clamp(s.start), s.start);
+    Some(Segment { end1, end2, steps, start: clamp(s.start), length: distance(end1, end2), mirrored: s.mirrored, weight: s.weight })
+}
+
+fn clip_to_rect(segments: &[Segment], rect: Rect) -> Vec<Segment> {
+    segments.iter().filter_map(|s| clip_segment(s, rect)).collect()
+}
+
+// every lattice point on the segment, end1 and end2 included
+fn lattice_points(s: &Segment) -> impl Iterator<Item=Point> {
+    let (start, end) = (s.end1, s.end2);
+    let dx = (end.x() - start.x()).signum();
+    let dy = (end.y() - start.y()).signum();
+    let length = (end.x() - start.x()).abs() + (end.y() - start.y()).abs();
+    (0..=length).map(move |i| Point::new(start.x() + i * dx, start.y() + i * dy))
+}
+
+fn coverage(segments: &[Segment]) -> HashSet<Point> {
+    segments.iter().flat_map(lattice_points).collect()
+}
+
+fn shared_coverage(coverage1: &HashSet<Point>, coverage2: &HashSet<Point>) -> HashSet<Point> {
+    coverage1.intersection(coverage2).cloned().collect()
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// the wire's geometry as maximal straight runs, sorted: the same for any walk tracing the same lines
+fn canonical_runs(segments: &[Segment]) -> Vec<(Point, Point)> {
+    let (horizontals, verticals) = split_on_direction(normalize(segments.to_vec()));
+    let mut runs: Vec<(Point, Point)> = vec!();
+    for (mut group, axis) in [(horizontals, Axis::Horizontal), (verticals, Axis::Vertical)] {
+        group.sort_by_key(|s| (axis.across(s.end1), axis.along(s.end1)));
+        let mut current: Option<(Point, Point)> = None;
+        for s in group {
+            current = match current {
+                Some((start, end)) if axis.across(end) == axis.across(s.end1) && axis.along(s.end1) <= axis.along(end) => {
+                    Some((start, if axis.along(s.end2) > axis.along(end) { s.end2 } else { end }))
+                }
+                Some(run) => {
+                    runs.push(run);
+                    Some((s.end1, s.end2))
+                }
+                None => Some((s.end1, s.end2)),
+            };
+        }
+        runs.extend(current);
+    }
+    runs
+}
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+// FNV-1a over the canonical runs, so it is stable across platforms and releases
+fn fingerprint(segments: &[Segment]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for (start, end) in canonical_runs(segments) {
+        for coord in [start.x(), start.y(), end.x(), end.y()].iter() {
+            hash = fnv1a(hash, &coord.to_le_bytes());
+        }
+    }
+    hash
+}
+
+fn distance(p: Point, origin: Point) -> i32 {
+    (p.x() - origin.x()).abs() + (p.y() - origin.y()).abs()
+}
+
+// what part 1 measures the distance from the origin with, see --metric
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum Metric {
+    Manhattan,
+    Chebyshev,
+    // picks the same point as euclidean distance while staying an integer
+    SquaredEuclidean,
+    // manhattan distance on a torus of this many cells, going either way round, see --wrap
+    Toroidal { width: i32, height: i32 },
+}
+
+// the shorter way round a ring of size cells
+fn ring_distance(d: i32, size: i32) -> i32 {
+    let d = d.rem_euclid(size);
+    min(d, size - d)
+}
+
+impl Metric {
+    fn parse(name: &str) -> Option<Metric> {
+        match name {
+            "manhattan" => Some(Metric::Manhattan),
+            "chebyshev" => Some(Metric::Chebyshev),
+            "euclidean" => Some(Metric::SquaredEuclidean),
+            _ => None,
+        }
+    }
+
+    fn measure(self, p: Point, origin: Point) -> i64 {
+        match self {
+            Metric::Manhattan => distance(p, origin) as i64,
+            Metric::Chebyshev => max((p.x() - origin.x()).abs(), (p.y() - origin.y()).abs()) as i64,
+            Metric::SquaredEuclidean => squared_distance(p, origin),
+            Metric::Toroidal { width, height } =>
+                (ring_distance(p.x() - origin.x(), width) + ring_distance(p.y() - origin.y(), height)) as i64,
+        }
+    }
+
+    // how measure gets its value, e.g. "|3| + |-4| = 7"
+    fn arithmetic(self, p: Point, origin: Point) -> String {
+        let (dx, dy) = (p.x() - origin.x(), p.y() - origin.y());
+        match self {
+            Metric::Manhattan => format!("|{}| + |{}| = {}", dx, dy, self.measure(p, origin)),
+            Metric::Chebyshev => format!("max(|{}|, |{}|) = {}", dx, dy, self.measure(p, origin)),
+            Metric::SquaredEuclidean => format!("{}^2 + {}^2 = {}", dx, dy, self.measure(p, origin)),
+            Metric::Toroidal { width, height } => format!("min(|{}|, {} - |{}|) + min(|{}|, {} - |{}|) = {}",
+                                                          dx, width, dx, dy, height, dy, self.measure(p, origin)),
+        }
+    }
+
+    // a lower bound on the measure of every point of s. The others only grow with |dx| and |dy|,
+    // and the nearest point of an axis aligned segment has the smallest of both; going round the
+    // torus can be shorter, so there it bounds nothing
+    fn to_segment(self, s: &Segment, p: Point) -> i64 {
+        match self {
+            Metric::Toroidal { .. } => 0,
+            _ => self.measure(nearest_point(s, p), p),
+        }
+    }
 }
 
-fn distance(p: Point) -> i32 {
-    p.x.abs() + p.y.abs()
+fn parse_point(s: &str) -> Option<Point> {
+    let mut coords = s.split(',').map(|c| c.trim().parse::<i32>());
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some(Point::new(x, y)),
+        _ => None
+    }
+}
+
+// "x1,y1,x2,y2", corners in any order
+fn parse_rect(s: &str) -> Option<Rect> {
+    let coords: Vec<i32> = s.split(',').map(|c| c.trim().parse()).collect::<std::result::Result<_, _>>().ok()?;
+    if coords.len() != 4 {
+        return None;
+    }
+    Some(Rect {
+        min: Point::new(min(coords[0], coords[2]), min(coords[1], coords[3])),
+        max: Point::new(max(coords[0], coords[2]), max(coords[1], coords[3])),
+    })
 }
 
-fn closest_intersect(path1: Vec<Segment>, path2: Vec<Segment>) -> (i32, PointWithCost, PointWithCost) {
+// wire 1 segments searched between calls to a progress callback
+const PROGRESS_INTERVAL: usize = 1024;
+
+// part 1 is measured from reference by metric, but only a start shared by both wires is left out.
+// With exclude_radius above 0 both parts also skip everything that close to a shared start, and
+// crossings inside any of obstacles are skipped too. Every point where the wires run along each
+// other counts, not just the ends of the stretch, so how the moves are split never changes the
+// answers. progress, when given, is called with (segments searched, wire 1 segments) every
+// PROGRESS_INTERVAL segments and once at the end. Returns the part 1 distance and both winners, None
+// when nothing is left to count
+fn closest_intersect(path1: Vec<Segment>, path2: Vec<Segment>, reference: Point, metric: Metric, exclude_radius: i32, obstacles: &[Rect], mut progress: Option<&mut dyn FnMut(usize, usize)>) -> Option<(i64, PointWithCost, PointWithCost)> {
+    let start = shared_start(&path1, &path2);
+    let excluded = |p: Point| (exclude_radius > 0 && start.is_some_and(|s| distance(p, s) <= exclude_radius))
+        || obstacles.iter().any(|o| o.contains(p));
     let (horizontals, verticals) = split_on_direction(path2);
-    let mut closest_intersect = PointWithCost { point: Point { x: 10000000, y: 10000000 }, cost: 10000000 };
-    let mut closest_by_path = PointWithCost { point: Point { x: 10000000, y: 10000000 }, cost: 10000000 };
-    let mut closest_distance = 100000000;
-    for segment in path1 {
-        let is = intersects(segment, &horizontals, &verticals);
-        for i in is {
-            let dist = distance(i.point);
-            if dist < closest_distance && dist != 0 {
-                closest_distance = dist;
-                closest_intersect = i;
+    let mut closest_intersect: Option<(i64, PointWithCost)> = None;
+    let mut closest_by_path: Option<PointWithCost> = None;
+    let mut is: Vec<PointWithCost> = vec!();
+    let total = path1.len();
+    for (done, segment) in path1.into_iter().enumerate() {
+        if done % PROGRESS_INTERVAL == 0 {
+            if let Some(report) = progress.as_mut() {
+                report(done, total);
+            }
+        }
+        is.clear();
+        intersects_with(segment, &horizontals, &verticals, OverlapPoints::Every, &mut is);
+        for i in is.iter().copied().filter(|i| !excluded(i.point)) {
+            let dist = metric.measure(i.point, reference);
+            if closest_intersect.is_none_or(|(d, _)| dist < d) && Some(i.point) != start {
+                closest_intersect = Some((dist, i));
             }
-            if i.cost < closest_by_path.cost && i.cost > 0 {
-                closest_by_path = i;
+            if closest_by_path.is_none_or(|c| i.cost < c.cost) && i.cost > Steps(0) {
+                closest_by_path = Some(i);
             }
         }
     }
-    (closest_distance, closest_intersect, closest_by_path)
+    if let Some(report) = progress {
+        report(total, total);
+    }
+    let (closest_distance, closest_intersect) = closest_intersect?;
+    Some((closest_distance, closest_intersect, closest_by_path?))
 }
 
-fn main() {
-    let mut path_strings: Vec<String> = vec!();
-    if let Ok(lines) = read_lines("input.txt") {
-        for line in lines {
-            if let Ok(path_string) = line {
-                path_strings.push(path_string);
+// the answers to both parts for one input
+#[derive(Copy, Debug, Clone, PartialEq)]
+struct Solution {
+    distance: Distance,
+    distance_at: Point,
+    steps: Steps,
+    steps_at: Point,
+}
+
+// None when the wires never cross away from a shared start
+fn solve(path1: Vec<Segment>, path2: Vec<Segment>, reference: Point) -> Option<Solution> {
+    let (distance, by_distance, by_steps) = closest_intersect(path1, path2, reference, Metric::Manhattan, 0, &[], None)?;
+    Some(Solution { distance: Distance(distance as i32), distance_at: by_distance.point, steps: by_steps.cost, steps_at: by_steps.point })
+}
+
+// what changed from a to b, e.g. "distance changed 159 -> 135"; empty when they agree
+fn diff_solutions(a: &Solution, b: &Solution) -> Vec<String> {
+    let mut changes: Vec<String> = vec!();
+    if a.distance != b.distance {
+        changes.push(format!("distance changed {} -> {}", a.distance, b.distance));
+    }
+    if a.distance_at != b.distance_at {
+        changes.push(format!("closest intersection moved {} -> {}", a.distance_at, b.distance_at));
+    }
+    if a.steps != b.steps {
+        changes.push(format!("steps changed {} -> {}", a.steps, b.steps));
+    }
+    if a.steps_at != b.steps_at {
+        changes.push(format!("fewest-steps intersection moved {} -> {}", a.steps_at, b.steps_at));
+    }
+    changes
+}
+
+fn all_intersections(path1: &[Segment], path2: &[Segment]) -> Vec<PointWithCost> {
+    all_intersections_with(path1, path2, OverlapPoints::Ends)
+}
+
+fn all_intersections_with(path1: &[Segment], path2: &[Segment], overlap: OverlapPoints) -> Vec<PointWithCost> {
+    let (horizontals, verticals) = split_on_direction(path2.to_vec());
+    let mut all: Vec<PointWithCost> = vec!();
+    for segment in path1 {
+        intersects_with(*segment, &horizontals, &verticals, overlap, &mut all);
+    }
+    all
+}
+
+// the move of a wire walking its steps'th step, zero length moves skipped. Where one move ends and
+// the next begins, leaving picks the next one
+fn move_at(moves: &[PathSegment], steps: i32, leaving: bool) -> usize {
+    let mut walked = 0;
+    for (index, m) in moves.iter().enumerate().filter(|(_, m)| m.distance != 0) {
+        walked += m.distance;
+        if walked > steps || (walked == steps && !leaving) {
+            return index;
+        }
+    }
+    moves.len().saturating_sub(1)
+}
+
+// every point where a segment of one wire meets one of the other, as all_intersections, with the
+// segments and moves of each wire that meet there; moves are what the segments were built from
+fn intersections(path1: &[Segment], path2: &[Segment], moves: [&[PathSegment]; 2], origin: Point, overlap: OverlapPoints) -> Vec<Intersection> {
+    let mut all: Vec<Intersection> = vec!();
+    let mut found: Vec<PointWithCost> = vec!();
+    for (i, a) in path1.iter().enumerate() {
+        for (j, b) in path2.iter().enumerate() {
+            let single = std::slice::from_ref(b);
+            let (horizontals, verticals): (&[Segment], &[Segment]) = if Axis::of(b) == Axis::Vertical { (&[], single) } else { (single, &[]) };
+            found.clear();
+            intersects_with(*a, horizontals, verticals, overlap, &mut found);
+            all.extend(found.iter().map(|f| Intersection::new(f.point, (i, a), (j, b), f.kind, moves, origin)));
+        }
+    }
+    all
+}
+
+// the cheapest meeting of the wires at p as intersections would give it, None if p is not on both
+fn intersection_at(path1: &[Segment], path2: &[Segment], moves: [&[PathSegment]; 2], origin: Point, p: Point) -> Option<Intersection> {
+    let on = |path: &[Segment]| -> Vec<usize> { (0..path.len()).filter(|&i| contains(&path[i], p)).collect() };
+    let (on1, on2) = (on(path1), on(path2));
+    on1.iter().flat_map(|&i| on2.iter().map(move |&j| (i, j)))
+        .map(|(i, j)| {
+            let (a, b) = (&path1[i], &path2[j]);
+            let kind = if Axis::of(a) == Axis::of(b) { CrossingKind::Overlap } else { crossing_kind(p, a, b) };
+            Intersection::new(p, (i, a), (j, b), kind, moves, origin)
+        })
+        .min_by_key(|i| i.cost())
+}
+
+// one per point other than exclude, the cheapest where several meet there, in the order the points
+// first come up; key gives the point and cost of each
+fn cheapest_per_point<T: Copy>(all: &[T], exclude: Option<Point>, key: impl Fn(&T) -> (Point, Steps)) -> Vec<T> {
+    let mut distinct: Vec<T> = vec!();
+    let mut index: HashMap<Point, usize> = HashMap::new();
+    for i in all.iter().filter(|i| Some(key(i).0) != exclude) {
+        let (point, cost) = key(i);
+        match index.get(&point) {
+            Some(&at) if cost < key(&distinct[at]).1 => distinct[at] = *i,
+            Some(_) => {}
+            None => {
+                index.insert(point, distinct.len());
+                distinct.push(*i);
             }
         }
     }
-//    let path_strings_0: Vec<PathSegment> = path_strings[0].split(',').map(toPathSegment).collect();
-//    let path_strings_1: Vec<PathSegment> = path_strings[1].split(',').map(toPathSegment).collect();
-    let path_strings_0: Vec<PathSegment> = "R75,D30,R83,U83,L12,D49,R71,U7,L72".split(',').map(toPathSegment).collect();
-    let path_strings_1: Vec<PathSegment> = "U62,R66,U55,R34,D71,R55,D58,R83".split(',').map(toPathSegment).collect();
+    distinct
+}
+
+fn distinct_points(all: &[Intersection], exclude: Option<Point>) -> Vec<Intersection> {
+    cheapest_per_point(all, exclude, |i| (i.point, i.cost()))
+}
+
+// how a stretch where the wires run along each other is counted
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum OverlapCount {
+    PerRun,
+    PerPoint,
+}
 
-    let segments_0 = normalize(path_to_segments(path_strings_0));
-    let segments_1 = normalize(path_to_segments(path_strings_1));
-    println!("{}", segments_0.len());
-    println!("{}", segments_1.len());
-    let (dist, inter, inter2) = closest_intersect(segments_0, segments_1);
-    println!("TASK 1: dist: {} for {}", dist, inter.point);
-    println!("TASK 2: dist: {} for {}", inter2.cost, inter2.point);
+// the shared stretch of two segments on the same line, if any
+fn overlap(a: &Segment, b: &Segment) -> Option<(Point, Point)> {
+    let horizontal = a.end1.y() == a.end2.y() && b.end1.y() == b.end2.y() && a.end1.y() == b.end1.y() && a.end1.x() != a.end2.x() && b.end1.x() != b.end2.x();
+    let vertical = a.end1.x() == a.end2.x() && b.end1.x() == b.end2.x() && a.end1.x() == b.end1.x();
+    if !horizontal && !vertical {
+        return None;
+    }
+    let start = Point::new(max(a.end1.x(), b.end1.x()), max(a.end1.y(), b.end1.y()));
+    let end = Point::new(min(a.end2.x(), b.end2.x()), min(a.end2.y(), b.end2.y()));
+    if start.x() > end.x() || start.y() > end.y() {
+        return None;
+    }
+    Some((start, end))
 }
 
-fn read_lines<P>(filename: P) -> Result<Lines<BufReader<File>>>
-    where P: AsRef<Path>, {
-    let file = File::open(filename)?;
-    Ok(BufReader::new(file).lines())
-}
\ No newline at end of file
+// the segments whose key falls in [low, high], from a slice sorted by that key
+fn key_range(sorted: &[Segment], key: impl Fn(&Segment) -> i32, low: i32, high: i32) -> &[Segment] {
+    let start = sorted.partition_point(|s| key(s) < low);
+    let end = sorted.partition_point(|s| key(s) <= high);
+    &sorted[start..end]
+}
+
+// whether the wires meet anywhere but a shared start; stops at the first such point and allocates
+// nothing
+fn wires_intersect(path1: &[Segment], path2: &[Segment]) -> bool {
+    let start = shared_start(path1, path2);
+    for a in path1 {
+        for b in path2 {
+            if Axis::of(a) == Axis::of(b) {
+                // an overlap reaches past the start unless it is just that point
+                if let Some((end1, end2)) = overlap(a, b) {
+                    if end1 != end2 || Some(end1) != start {
+                        return true;
+                    }
+                }
+            } else {
+                let (v, h) = if Axis::of(a) == Axis::Vertical { (a, b) } else { (b, a) };
+                if between(v.end1.x(), h.end1.x(), h.end2.x()) && between(h.end1.y(), v.end1.y(), v.end2.y())
+                    && Some(Point::new(v.end1.x(), h.end1.y())) != start {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+// number of distinct points where the wires meet, a shared start excluded, without working out
+// costs. With the other wire sorted by position each segment only looks at the candidates in its span
+fn count_intersections(path1: &[Segment], path2: &[Segment], policy: OverlapCount) -> usize {
+    let start = shared_start(path1, path2);
+    // sorting and searching cost (len1 + len2) * log of the indexed length, so index the shorter wire
+    let (path1, path2) = if path2.len() > path1.len() { (path2, path1) } else { (path1, path2) };
+    let (mut horizontals, mut verticals) = split_on_direction(path2.to_vec());
+    horizontals.sort_unstable_by_key(|s| s.end1.y());
+    verticals.sort_unstable_by_key(|s| s.end1.x());
+    let mut points: Vec<Point> = vec!();
+    let mut runs: Vec<Segment> = vec!();
+    for segment in path1 {
+        // segments are normalized, so end1 is the low end of each
+        let vertical = segment.end1.x() == segment.end2.x();
+        let (parallel, perpendicular) = if vertical {
+            (key_range(&verticals, |s| s.end1.x(), segment.end1.x(), segment.end1.x()), key_range(&horizontals, |s| s.end1.y(), segment.end1.y(), segment.end2.y()))
+        } else {
+            (key_range(&horizontals, |s| s.end1.y(), segment.end1.y(), segment.end1.y()), key_range(&verticals, |s| s.end1.x(), segment.end1.x(), segment.end2.x()))
+        };
+        for other in perpendicular {
+            let (v, h) = if vertical { (segment, other) } else { (other, segment) };
+            if between(v.end1.x(), h.end1.x(), h.end2.x()) && between(h.end1.y(), v.end1.y(), v.end2.y()) {
+                points.push(Point::new(v.end1.x(), h.end1.y()));
+            }
+        }
+        for other in parallel {
+            match (overlap(segment, other), policy) {
+                (Some((end1, end2)), OverlapCount::PerRun) if end1 != end2 => runs.push(Segment { end1, end2, steps: 0, start: end1, length: distance(end1, end2), mirrored: false, weight: 1 }),
+                (Some((end1, end2)), _) => points.extend(lattice_points(&Segment { end1, end2, steps: 0, start: end1, length: distance(end1, end2), mirrored: false, weight: 1 })),
+                (None, _) => {}
+            }
+        }
+    }
+    // corners shared by consecutive segments are found twice
+    points.sort_unstable_by_key(|p| (p.x(), p.y()));
+    points.dedup();
+    runs.sort_unstable_by_key(|r| (r.end1.x(), r.end1.y(), r.end2.x(), r.end2.y()));
+    runs.dedup_by(|a, b| a.end1 == b.end1 && a.end2 == b.end2);
+    // a point inside a run is already counted by the run
+    let loose = points.iter().filter(|p| Some(**p) != start && !runs.iter().any(|r| contains(r, **p))).count();
+    loose + runs.len()
+}
+
+// one entry per crossing point other than exclude, with the cheapest cost seen there. A corner
+// shared by two segments of a wire or an overlap of a single cell otherwise shows up twice
+fn distinct_except(all: &[PointWithCost], exclude: Option<Point>) -> Vec<PointWithCost> {
+    cheapest_per_point(all, exclude, |i| (i.point, i.cost))
+}
+
+// the point of s nearest p
+fn nearest_point(s: &Segment, p: Point) -> Point {
+    Point::new(p.x().max(min(s.end1.x(), s.end2.x())).min(max(s.end1.x(), s.end2.x())),
+               p.y().max(min(s.end1.y(), s.end2.y())).min(max(s.end1.y(), s.end2.y())))
+}
+
+// distance from p to the nearest point of s
+fn distance_to_segment(s: &Segment, p: Point) -> i32 {
+    distance(nearest_point(s, p), p)
+}
+
+// a wire 1 segment still to search, or an intersection found in one
+const SEGMENT_ENTRY: u8 = 0;
+const HIT_ENTRY: u8 = 1;
+
+// (distance, entry kind, cost, x, y, index into path1 or hits)
+type HeapEntry = (i64, u8, i32, i32, i32, usize);
+
+struct IntersectionsByDistance {
+    path1: Vec<Segment>,
+    horizontals: Vec<Segment>,
+    verticals: Vec<Segment>,
+    origin: Point,
+    // a shared start, which is never yielded
+    exclude: Option<Point>,
+    metric: Metric,
+    // segments sort before hits at the same distance, so a point is only yielded once every
+    // segment that could reach it was searched
+    heap: BinaryHeap<Reverse<HeapEntry>>,
+    hits: Vec<PointWithCost>,
+    yielded: HashSet<Point>,
+}
+
+impl Iterator for IntersectionsByDistance {
+    type Item = PointWithCost;
+
+    fn next(&mut self) -> Option<PointWithCost> {
+        while let Some(Reverse((_, kind, _, _, _, index))) = self.heap.pop() {
+            if kind == HIT_ENTRY {
+                let hit = self.hits[index];
+                if self.yielded.insert(hit.point) {
+                    return Some(hit);
+                }
+                continue;
+            }
+            let mut found: Vec<PointWithCost> = vec!();
+            intersects(self.path1[index], &self.horizontals, &self.verticals, &mut found);
+            let exclude = self.exclude;
+            for hit in found.into_iter().filter(|h| Some(h.point) != exclude) {
+                self.heap.push(Reverse((self.metric.measure(hit.point, self.origin), HIT_ENTRY, hit.cost.0, hit.point.x(), hit.point.y(), self.hits.len())));
+                self.hits.push(hit);
+            }
+        }
+        None
+    }
+}
+
+// distinct intersections other than a shared start nearest reference by metric first, each with its
+// cheapest cost, searching wire 1 only as far out as the caller keeps pulling
+fn intersections_by_metric(path1: &[Segment], path2: &[Segment], reference: Point, metric: Metric) -> impl Iterator<Item=PointWithCost> {
+    let (horizontals, verticals) = split_on_direction(path2.to_vec());
+    let heap = path1.iter().enumerate()
+        .map(|(i, s)| Reverse((metric.to_segment(s, reference), SEGMENT_ENTRY, 0, 0, 0, i)))
+        .collect();
+    let exclude = shared_start(path1, path2);
+    IntersectionsByDistance { path1: path1.to_vec(), horizontals, verticals, origin: reference, exclude, metric, heap, hits: vec!(), yielded: HashSet::new() }
+}
+
+// where the wire meets an axis aligned line, in order along the wire; a stretch of the wire lying on
+// the line gives its two ends. For an infinite line pass ends at i32::MIN and i32::MAX; the line is
+// cut to the wire's bounds first, so only its ends are looked at
+fn intersections_with_line(segments: &[Segment], line: Segment) -> Vec<Point> {
+    if segments.is_empty() {
+        return vec!();
+    }
+    let rect = render::bounds(&[segments]);
+    let (x1, x2) = (max(min(line.end1.x(), line.end2.x()), rect.min.x()), min(max(line.end1.x(), line.end2.x()), rect.max.x()));
+    let (y1, y2) = (max(min(line.end1.y(), line.end2.y()), rect.min.y()), min(max(line.end1.y(), line.end2.y()), rect.max.y()));
+    if x1 > x2 || y1 > y2 {
+        return vec!();
+    }
+    let (end1, end2) = (Point::new(x1, y1), Point::new(x2, y2));
+    let wall = Segment { end1, end2, steps: 0, start: end1, length: distance(end1, end2), mirrored: false, weight: 1 };
+    let (horizontals, verticals) = split_on_direction(vec![wall]);
+    let mut points: Vec<Point> = vec!();
+    let mut found: Vec<PointWithCost> = vec!();
+    for segment in segments {
+        found.clear();
+        intersects_with(*segment, &horizontals, &verticals, OverlapPoints::Ends, &mut found);
+        // overlap ends come low coordinate first, not in the wire's direction
+        found.sort_by_key(|i| distance(i.point, segment.start));
+        points.extend(found.iter().map(|i| i.point));
+    }
+    // a corner on the line is found from the segments either side of it
+    points.dedup();
+    points
+}
+
+// distinct intersections kept by keep other than exclude, nearest reference first. Only segments
+// passing near are tested, so a narrow query stays cheap on big inputs
+fn intersections_where(path1: &[Segment], path2: &[Segment], reference: Point, exclude: Option<Point>,
+                       near: impl Fn(&Segment) -> bool, keep: impl Fn(Point) -> bool) -> Vec<PointWithCost> {
+    let (horizontals, verticals) = split_on_direction(path2.iter().filter(|s| near(s)).copied().collect());
+    let mut found: Vec<PointWithCost> = vec!();
+    for segment in path1.iter().filter(|s| near(s)) {
+        intersects(*segment, &horizontals, &verticals, &mut found);
+    }
+    found.retain(|i| keep(i.point));
+    let mut kept = distinct_except(&found, exclude);
+    kept.sort_by_key(|i| (distance(i.point, reference), i.cost));
+    kept
+}
+
+// distinct intersections at most radius from reference, nearest first, a shared start left out
+fn intersections_within(path1: &[Segment], path2: &[Segment], reference: Point, radius: i32) -> Vec<PointWithCost> {
+    intersections_where(path1, path2, reference, shared_start(path1, path2),
+                        |s| distance_to_segment(s, reference) <= radius, |p| distance(p, reference) <= radius)
+}
+
+// the crossing other than a shared start farthest from reference, ties going to the smaller x then
+// y as in stats
+fn farthest_intersection(path1: &[Segment], path2: &[Segment], reference: Point) -> Option<PointWithCost> {
+    let start = shared_start(path1, path2);
+    all_intersections(path1, path2).into_iter()
+        .filter(|i| Some(i.point) != start)
+        .max_by_key(|i| (distance(i.point, reference), -i.point.x(), -i.point.y()))
+}
+
+// distinct intersections other than a shared start whose distance from reference is in lo..=hi
+fn count_in_band(path1: &[Segment], path2: &[Segment], reference: Point, lo: i32, hi: i32) -> usize {
+    distinct_except(&all_intersections(path1, path2), shared_start(path1, path2)).iter()
+        .filter(|i| between(distance(i.point, reference), lo, hi))
+        .count()
+}
+
+// distinct intersections other than a shared start inside rect, edges included, nearest reference
+// first; for many queries on the same wires keep the PreparedWires
+fn intersections_in_rect(path1: &[Segment], path2: &[Segment], reference: Point, rect: Rect) -> Vec<PointWithCost> {
+    PreparedWire::new(path1).intersections_in_rect(&PreparedWire::new(path2), reference, rect)
+}
+
+// where the wire starts, i.e. the shared origin of both wires
+fn wire_start(segments: &[Segment]) -> Point {
+    segments.first().map_or(Point::ORIGIN, |s| s.start)
+}
+
+// the start of both wires if they have the same one; only then is their crossing there trivial. A
+// wire clipped to a window may begin part way along, where it enters, which is no start
+fn shared_start(path1: &[Segment], path2: &[Segment]) -> Option<Point> {
+    let from_start = |path: &[Segment]| path.first().is_none_or(|s| s.steps == 0);
+    Some(wire_start(path1)).filter(|start| *start == wire_start(path2) && from_start(path1) && from_start(path2))
+}
+
+// nearest points of intervals [a1, a2] and [b1, b2], the lowest ones where they overlap
+fn nearest_on_axis(a1: i32, a2: i32, b1: i32, b2: i32) -> (i32, i32) {
+    if a2 < b1 {
+        (a2, b1)
+    } else if b2 < a1 {
+        (a1, b2)
+    } else {
+        (max(a1, b1), max(a1, b1))
+    }
+}
+
+// the pair of points, one on each wire, that are nearest to each other. Segments both leaving the
+// shared start always meet there, so that pair is skipped; ties go to the lowest points by (x, y)
+fn closest_approach(path1: &[Segment], path2: &[Segment]) -> Option<(Point, Point, i64)> {
+    let start = shared_start(path1, path2);
+    let mut best: Option<(Point, Point, i64)> = None;
+    for a in path1 {
+        for b in path2 {
+            if start.is_some_and(|start| contains(a, start) && contains(b, start)) {
+                continue;
+            }
+            // normalized, so end1 holds the low coordinates
+            let (ax, bx) = nearest_on_axis(a.end1.x(), a.end2.x(), b.end1.x(), b.end2.x());
+            let (ay, by) = nearest_on_axis(a.end1.y(), a.end2.y(), b.end1.y(), b.end2.y());
+            let (p, q) = (Point::new(ax, ay), Point::new(bx, by));
+            let d = distance(p, q) as i64;
+            let better = best.is_none_or(|(bp, bq, bd)| (d, p.x(), p.y(), q.x(), q.y()) < (bd, bp.x(), bp.y(), bq.x(), bq.y()));
+            if better {
+                best = Some((p, q, d));
+            }
+        }
+    }
+    best
+}
+
+// length weighted average of the segment midpoints, rounded; the origin for an empty wire
+fn centroid(segments: &[Segment]) -> Point {
+    let total: i64 = segments.iter().map(|s| distance(s.end1, s.end2) as i64).sum();
+    if total == 0 {
+        return Point::ORIGIN;
+    }
+    let weighted = |coord: fn(&Point) -> i32| -> i32 {
+        let sum: i64 = segments.iter().map(|s| distance(s.end1, s.end2) as i64 * (coord(&s.end1) + coord(&s.end2)) as i64).sum();
+        (sum as f64 / (2 * total) as f64).round() as i32
+    };
+    Point::new(weighted(|p| p.x()), weighted(|p| p.y()))
+}
+
+// the crossing minimizing alpha * distance from reference + beta * steps; part 1 is alpha=1, beta=0
+// and part 2 alpha=0, beta=1
+fn best_weighted(path1: &[Segment], path2: &[Segment], reference: Point, alpha: f64, beta: f64) -> Option<PointWithCost> {
+    let start = shared_start(path1, path2);
+    let score = |i: &PointWithCost| alpha * distance(i.point, reference) as f64 + beta * i.cost.0 as f64;
+    all_intersections(path1, path2).into_iter()
+        .filter(|i| Some(i.point) != start)
+        .min_by(|a, b| score(a).total_cmp(&score(b)))
+}
+
+// the best crossing under each metric --all-metrics reports, origin excluded
+#[derive(Copy, Debug, Clone, Default)]
+struct BestPerMetric {
+    manhattan: Option<Intersection>,
+    steps: Option<Intersection>,
+    euclidean: Option<Intersection>,
+}
+
+fn squared_distance(p: Point, origin: Point) -> i64 {
+    let (dx, dy) = ((p.x() - origin.x()) as i64, (p.y() - origin.y()) as i64);
+    dx * dx + dy * dy
+}
+
+// one pass over intersections output, measured from reference, start left out; ties keep the first
+// seen
+fn best_per_metric(all: &[Intersection], reference: Point, start: Option<Point>) -> BestPerMetric {
+    let mut best = BestPerMetric::default();
+    let better = |current: Option<Intersection>, key: &dyn Fn(&Intersection) -> i64, i: &Intersection| {
+        current.is_none_or(|c| key(i) < key(&c))
+    };
+    for i in all.iter().filter(|i| Some(i.point) != start) {
+        if better(best.manhattan, &|c| distance(c.point, reference) as i64, i) {
+            best.manhattan = Some(*i);
+        }
+        if better(best.steps, &|c| c.cost().0 as i64, i) {
+            best.steps = Some(*i);
+        }
+        if better(best.euclidean, &|c| squared_distance(c.point, reference), i) {
+            best.euclidean = Some(*i);
+        }
+    }
+    best
+}
+
+// what the best crossing is judged by, see --objective
+#[derive(Copy, Debug, Clone, PartialEq)]
+enum Objective {
+    Distance,
+    StepsSum,
+    // the larger of the two wires' steps, i.e. when both signals have arrived
+    Minimax,
+    // the largest distance instead of the smallest
+    Farthest,
+}
+
+impl Objective {
+    fn parse(name: &str) -> Option<Objective> {
+        match name {
+            "distance" => Some(Objective::Distance),
+            "steps" => Some(Objective::StepsSum),
+            "minimax" => Some(Objective::Minimax),
+            "farthest" => Some(Objective::Farthest),
+            _ => None,
+        }
+    }
+}
+
+// the crossing of a and b other than start with the lowest max(steps on a, steps on b), lowest sum
+// on ties. Along an overlap both step counts are linear, so the max is lowest at an end or where
+// the two are equal
+fn minimax_crossing(a: &Segment, b: &Segment, start: Option<Point>) -> Option<(Point, i32)> {
+    let score = |p: Point| (max(cost_for_segment(p, a), cost_for_segment(p, b)), cost(p, a, b));
+    let mut candidates: Vec<Point> = vec!();
+    let axis = Axis::of(a);
+    if axis == Axis::of(b) {
+        let (lo, hi) = overlap(a, b)?;
+        candidates.extend(&[lo, hi]);
+        // the start does not count, so the point next to it stands in for an end there
+        if let Some(start) = start.filter(|s| lo != hi && (lo == *s || hi == *s)) {
+            let step = if lo == start { 1 } else { -1 };
+            candidates.push(axis.point(axis.along(start) + step, axis.across(start)));
+        }
+        let gap = |p: Point| cost_for_segment(p, a) - cost_for_segment(p, b);
+        let (g_lo, g_hi) = (gap(lo), gap(hi));
+        let (from, to) = (axis.along(lo), axis.along(hi));
+        if g_lo.signum() != g_hi.signum() && from != to {
+            // where the gap is 0, then the lattice points either side
+            let at = from + (g_lo as i64 * (to - from) as i64 / (g_lo - g_hi) as i64) as i32;
+            for t in &[at, at + 1] {
+                if between(*t, from, to) {
+                    candidates.push(axis.point(*t, axis.across(lo)));
+                }
+            }
+        }
+    } else {
+        let (v, h) = if axis == Axis::Vertical { (a, b) } else { (b, a) };
+        if !(between(v.end1.x(), h.end1.x(), h.end2.x()) && between(h.end1.y(), v.end1.y(), v.end2.y())) {
+            return None;
+        }
+        candidates.push(Point::new(v.end1.x(), h.end1.y()));
+    }
+    candidates.into_iter()
+        .filter(|p| Some(*p) != start)
+        .min_by_key(|p| (score(*p), p.x(), p.y()))
+        .map(|p| (p, score(p).0))
+}
+
+// the best crossing other than a shared start under objective and its score there, distances
+// measured from reference
+fn best_by(objective: Objective, path1: &[Segment], path2: &[Segment], reference: Point) -> Option<(Point, i32)> {
+    let start = shared_start(path1, path2);
+    match objective {
+        Objective::Distance => best_weighted(path1, path2, reference, 1.0, 0.0).map(|i| (i.point, distance(i.point, reference))),
+        Objective::StepsSum => best_weighted(path1, path2, reference, 0.0, 1.0).map(|i| (i.point, i.cost.0)),
+        Objective::Minimax => path1.iter()
+            .flat_map(|a| path2.iter().filter_map(move |b| minimax_crossing(a, b, start)))
+            .min_by_key(|(p, score)| (*score, p.x(), p.y())),
+        Objective::Farthest => farthest_intersection(path1, path2, reference).map(|i| (i.point, distance(i.point, reference))),
+    }
+}
+
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned()
+}
+
+// every value of a flag that may be given more than once
+fn arg_values(args: &[String], flag: &str) -> Vec<String> {
+    args.windows(2).filter(|w| w[0] == flag).map(|w| w[1].clone()).collect()
+}
+
+// --wrap W,H, see path_to_wrapped_segments
+fn wrap_size(args: &[String]) -> Option<(i32, i32)> {
+    arg_value(args, "--wrap").map(|w| {
+        let size: Vec<i32> = w.split(',').map(|c| c.trim().parse().ok().filter(|c| *c > 0)).collect::<Option<_>>()
+            .unwrap_or_else(|| fail(format!("invalid wrap size {}", w)));
+        match size[..] {
+            [width, height] => (width, height),
+            _ => fail(format!("invalid wrap size {}", w)),
+        }
+    })
+}
+
+// every --obstacle, see check_obstacles
+fn obstacles(args: &[String]) -> Vec<Rect> {
+    arg_values(args, "--obstacle").iter()
+        .map(|o| parse_rect(o).unwrap_or_else(|| fail(format!("invalid obstacle {}", o))))
+        .collect()
+}
+
+const USAGE: &str = "\
+usage: day3 [solve|render|stats|nearest|route|graph|edit|transform|validate|bench] [options]
+
+commands:
+  solve                    print the part 1 and part 2 answers (default)
+  render                   draw the wires, see --format
+  stats                    print per-wire and combined metrics
+  nearest --point X,Y      the point of each wire nearest X,Y
+  route --from X,Y --to X,Y
+                           shortest way between two points along the wires
+  graph                    print both wires as an edge list, \"x1,y1 x2,y2 length wire\"
+  edit                     change the input by the lines of --script, or of stdin, and
+                           print both answers after each: append N MOVE adds a move to
+                           wire N, pop N removes its last, replace N I MOVE changes its
+                           Ith, undo and redo step through the edits
+  transform                print the input again, changed as the transform options say
+                           in the order they are listed
+  validate                 check that the input parses and re-check the part 2 answer
+                           wire by wire
+  bench                    time counting the crossings against listing them all
+
+options:
+  --input FILE             wire file, one wire per line (default input.txt). A wire
+                           written as xN:MOVES costs N per step in part 2, one
+                           written as (X,Y): MOVES starts at X,Y
+  --emit-bin FILE          write the input in the compact binary form and exit; any
+                           input in that form is read as such; wires with a start or a
+                           weight cannot be written
+  --format bin             every command but render: the inputs are in the compact binary
+                           form, instead of telling by how each file starts
+  --compare OLD NEW        compare the answers, crossings and wire paths of two inputs
+  --batch MANIFEST         solve every input file listed in MANIFEST, one per line, and
+                           count the crossings of each
+  --overlap-count run|point
+                           --batch: count a stretch where the wires run along each other
+                           once (default) or once per point
+  --syntax aoc|xy          move notation (default aoc)
+  --signed-distance        a negative distance reverses the move, R-5 is L5; rejected
+                           otherwise
+  --pair-delimiter D       both wires on one line, separated by D
+  --start X,Y              shared start of both wires (default 0,0)
+  --origin X,Y             solve: the point part 1 distances and --list are measured
+                           from (default the start); the start is still never counted
+  --max-segments N         refuse wires with more than N segments (default unlimited)
+  --wrap W,H               the grid is a torus of W by H cells from 0,0, moves running off
+                           an edge come back at the other; part 1 goes either way round
+  --clip X1,Y1,X2,Y2       drop the parts of both wires outside this window; steps still
+                           count from the start, which part 1 measures from even outside
+  --obstacle X1,Y1,X2,Y2   refuse wires entering this rect, edges included; repeatable
+  --progress               solve: show on stderr how far the search has got
+  --coverage               solve: also count the lattice cells each wire covers
+  --objective distance|steps|minimax|farthest
+                           solve: also print the best intersection by this measure;
+                           minimax is the larger of the two wires' steps, farthest
+                           the largest distance
+  --metric manhattan|chebyshev|euclidean
+                           solve: distance part 1 is measured by (default manhattan);
+                           euclidean is reported squared
+  --exclude-radius R       solve: skip intersections at most R from a shared start in
+                           both parts (default 0, only the start itself)
+  --all-metrics            solve: also print the best intersection by manhattan
+                           distance, steps and euclidean distance
+  --list                   solve: also print every intersection, nearest first
+  --min-steps N, --max-steps N
+                           solve: --list only the intersections within these steps
+  --overlap-points ends|every
+                           solve: where the wires run along each other, --list the two
+                           ends of the shared stretch (default) or every point of it
+  --explain                solve: also show which moves meet at each winner and the
+                           arithmetic for its answer
+  --fingerprint            solve: also print a hash of each wire's geometry
+  --within R               solve: also list the intersections at most R from --origin
+  --rect X1,Y1,X2,Y2       solve: also list the intersections inside this rect, edges
+                           included, nearest --origin first
+  --band LO,HI             solve: also count the intersections LO to HI from --origin
+  --beyond D               solve: also print the nearest intersection farther than D
+                           from --origin by --metric
+  --wall X1,Y1,X2,Y2       solve: also print where each wire meets this horizontal or
+                           vertical line, in walking order
+  --output text|json       stats: report format (default text)
+  --regions                stats: also count the regions the wires enclose, slow on
+                           large inputs
+  --random                 generate both wires instead of reading a file
+  --seed N, --count N      random: generator seed (default 0) and moves per wire (default 301)
+  --verbose                trace segment building and intersection tests
+  --format FORMAT          render: braille, heatmap or pgm
+  --width N, --height N    render: canvas size (default width $COLUMNS if exported, else
+                           80, and height to keep the wires' proportions)
+  --window X1,Y1,X2,Y2     render: braille window
+  --focus W,H              render: a W by H braille window centred on the wires'
+                           length-weighted centre
+  --no-color               render: heatmap intensity characters instead of colors
+  --bins N                 render: pgm resolution
+  --steps N                render: only the first N steps of each wire
+  -o FILE                  render: output file
+  --script FILE            edit: read the edits from FILE
+  --runs N                 bench: runs to average over (default 10)
+  --wire N                 transform: change only wire N (default both)
+  --slice FROM,TO          transform: keep only the stretch walked between these steps
+  --reverse                transform: walk the wire back from its far end
+  --append FILE            transform: walk on from the end with the moves of the same
+                           wire in FILE
+  --rotate K               transform: turn K quarter turns counter-clockwise about 0,0
+  --mirror x|y             transform: negate x, swapping L and R, or y, swapping U and D
+  --translate DX,DY        transform: move the start by DX,DY
+";
+
+fn fail(message: impl fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+// --input, else a FILE right after the command, else input.txt
+fn input_path(args: &[String]) -> String {
+    if let Some(input) = arg_value(args, "--input") {
+        return input;
+    }
+    match (args.get(1), args.get(2)) {
+        (Some(command), Some(file)) if !command.starts_with('-') && !file.starts_with('-') => file.clone(),
+        _ => "input.txt".to_string()
+    }
+}
+
+// the moves of both wires
+type Wires = (Vec<PathSegment>, Vec<PathSegment>);
+
+// with a pair delimiter both wires come from the first non-empty line, split on it
+fn read_wires_with(filename: &str, options: ParseOptions, pair_delimiter: Option<&str>) -> std::result::Result<Wires, Error> {
+    read_wire_headers(filename, options, pair_delimiter).map(|(wires, _)| wires)
+}
+
+// what an input line gives for its wire besides the parsed moves: what comes before them, see
+// split_start and split_weight, and the moves as written, which --explain quotes
+#[derive(Debug, Clone, PartialEq)]
+struct WireHeader {
+    start: Option<Point>,
+    weight: i32,
+    tokens: Vec<String>,
+}
+
+const NO_HEADER: WireHeader = WireHeader { start: None, weight: 1, tokens: Vec::new() };
+
+// as read_wires_with, also returning the header of each wire
+fn read_wire_headers(filename: &str, options: ParseOptions, pair_delimiter: Option<&str>) -> std::result::Result<(Wires, [WireHeader; 2]), Error> {
+    let io_error = |source| Error::Io { path: filename.to_string(), source };
+    let mut input = BufReader::new(File::open(filename).map_err(io_error)?);
+    if options.binary || input.fill_buf().map_err(io_error)?.starts_with(compact::MAGIC) {
+        let wires = compact::read(input).map_err(|source| Error::Compact { path: filename.to_string(), source })?;
+        return Ok((wires, [NO_HEADER; 2]));
+    }
+    let lines = input.lines();
+    let mut wires: Vec<(Vec<PathSegment>, WireHeader)> = vec!();
+    for (number, line) in lines.enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parse_error = |source| Error::Parse { path: filename.to_string(), line: number + 1, source };
+        // offsets count from the start of the line, not of the part or of the moves after a header
+        let parse_wire = |part: &str, start: usize| {
+            let shifted = |e: ParseError, by: usize| ParseError { offset: e.offset + by, ..e };
+            let (wire_start, weighted, start_len) = split_start(part).map_err(|e| shifted(e, start))?;
+            let (weight, moves, weight_len) = split_weight(weighted).map_err(|e| shifted(e, start + start_len))?;
+            let path = parse_path(moves, options).map_err(|e| shifted(e, start + start_len + weight_len))?;
+            let tokens = moves.split(',').map(|token| token.trim().to_string()).collect();
+            Ok((path, WireHeader { start: wire_start, weight, tokens }))
+        };
+        match pair_delimiter {
+            Some(delimiter) => {
+                let mut start = 0;
+                for part in line.split(delimiter) {
+                    wires.push(parse_wire(part, start).map_err(parse_error)?);
+                    start += part.len() + delimiter.len();
+                }
+                break;
+            }
+            None => wires.push(parse_wire(&line, 0).map_err(parse_error)?),
+        }
+    }
+    if wires.len() != 2 {
+        return Err(Error::WireCount { path: filename.to_string(), found: wires.len() });
+    }
+    let (wire2, header2) = wires.pop().unwrap();
+    let (wire1, header1) = wires.pop().unwrap();
+    Ok(((wire1, wire2), [header1, header2]))
+}
+
+// every step of the wire costing weight
+fn weighted(segments: Vec<Segment>, weight: i32) -> Vec<Segment> {
+    segments.into_iter().map(|s| Segment { weight, ..s }).collect()
+}
+
+// obstacles are rects of cells with their edges included, so a wire running along an edge is inside
+// one. Reports the first move, wire 1 first, with a cell in an obstacle. With wrap the moves are
+// walked on that torus, as path_to_wrapped_segments lays them out
+fn check_obstacles(path: &str, wires: [(&[PathSegment], Point); 2], obstacles: &[Rect], wrap: Option<(i32, i32)>) -> std::result::Result<(), Error> {
+    if obstacles.is_empty() {
+        return Ok(());
+    }
+    for (wire, (moves, start)) in wires.iter().enumerate() {
+        let mut at = move_end(*start, &PathSegment { direction: 'R', distance: 0 }, wrap);
+        for (index, step) in moves.iter().enumerate() {
+            let pieces = match wrap {
+                Some(size) => path_to_wrapped_segments(vec![*step], at, size),
+                None => path_to_segments(vec![*step], at),
+            };
+            let end = move_end(at, step, wrap);
+            let entered = |obstacle: &Rect| -> Option<Point> {
+                for piece in &pieces {
+                    if let Some(inside) = clip_segment(piece, *obstacle) {
+                        // clip keeps the low coordinates in end1, the wire enters from whichever is nearer
+                        return Some(if distance(inside.end1, piece.start) <= distance(inside.end2, piece.start) { inside.end1 } else { inside.end2 });
+                    }
+                }
+                // the cell a wrapped move lands on may be in no piece
+                Some(end).filter(|p| obstacle.contains(*p) && step.distance > 0)
+            };
+            for obstacle in obstacles {
+                if let Some(cell) = entered(obstacle) {
+                    return Err(Error::EntersObstacle { path: path.to_string(), wire: wire + 1, index: index + 1, cell, obstacle: *obstacle });
+                }
+            }
+            at = end;
+        }
+    }
+    Ok(())
+}
+
+// the segments of a wire from its own start if the header gives one, else from origin
+fn wire_segments(path: Vec<PathSegment>, header: &WireHeader, origin: Point) -> Vec<Segment> {
+    weighted(normalize(coalesce(&path_to_segments(path, header.start.unwrap_or(origin)))), header.weight)
+}
+
+// guards the quadratic intersection search against runaway inputs
+fn check_segment_limit(path: &str, segments: [&[Segment]; 2], limit: Option<usize>) -> std::result::Result<(), Error> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    for (wire, s) in segments.iter().enumerate() {
+        if s.len() > limit {
+            return Err(Error::TooManySegments { path: path.to_string(), wire: wire + 1, found: s.len(), limit });
+        }
+    }
+    Ok(())
+}
+
+fn color_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+}
+
+fn styled(text: &str, ansi: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", ansi, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn width_of(values: &[i32]) -> usize {
+    values.iter().map(|v| v.to_string().len()).max().unwrap_or(0)
+}
+
+fn solve_summary(paths: [&[PathSegment]; 2], segments: [&[Segment]; 2], part1: (i64, Point), part2: (i32, Point), color: bool) -> String {
+    let moves = [paths[0].len() as i32, paths[1].len() as i32];
+    let counts = [segments[0].len() as i32, segments[1].len() as i32];
+    let lengths = [path_length(paths[0]), path_length(paths[1])];
+    let (mw, sw, lw) = (width_of(&moves), width_of(&counts), width_of(&lengths));
+    let aw = max(part1.0.to_string().len(), part2.0.to_string().len());
+    let mut out = String::new();
+    for wire in 0..2 {
+        out += &format!("{} {:>mw$} moves, {:>sw$} segments, length {:>lw$}\n",
+                        styled(&format!("Wire {}:", wire + 1), "1", color), moves[wire], counts[wire], lengths[wire],
+                        mw = mw, sw = sw, lw = lw);
+    }
+    for (part, (answer, point)) in [(1, part1), (2, (part2.0 as i64, part2.1))].iter() {
+        let answer = styled(&format!("{:>aw$}", answer, aw = aw), "1;32", color);
+        out += &format!("{} {} at {}\n", styled(&format!("Part {}:", part), "1", color), answer, point);
+    }
+    out
+}
+
+// what --explain needs to know about the wires besides the winners
+struct Explanation<'a> {
+    segments: [&'a [Segment]; 2],
+    moves: [&'a [PathSegment]; 2],
+    // the moves as written, empty where the input was not text
+    tokens: [&'a [String]; 2],
+    syntax: Syntax,
+    reference: Point,
+    metric: Metric,
+}
+
+// --explain: the move of each wire meeting at each part's winner and the arithmetic giving its answer
+fn explain(wires: &Explanation, part1: &Intersection, part2: &Intersection) -> String {
+    let mut out = String::new();
+    for (part, i) in [(1, part1), (2, part2)].iter() {
+        let p = i.point;
+        let token = |wire: usize| {
+            let index = i.move_index[wire];
+            wires.tokens[wire].get(index).cloned().unwrap_or_else(|| wires.syntax.token(&wires.moves[wire][index]))
+        };
+        out += &format!("Part {}: {} is where wire 1 move {}, {}, meets wire 2 move {}, {}\n",
+                        part, p, i.move_index[0] + 1, token(0), i.move_index[1] + 1, token(1));
+        if *part == 1 {
+            out += &format!("  distance from {}: {}\n", wires.reference, wires.metric.arithmetic(p, wires.reference));
+            continue;
+        }
+        for wire in 0..2 {
+            let s = &wires.segments[wire][i.segment_index[wire]];
+            let before = path_length(&wires.moves[wire][..i.move_index[wire]]);
+            let along = s.steps + distance(p, s.start) - before;
+            let sum = format!("{} before move {} + {} along it", before, i.move_index[wire] + 1, along);
+            let weighted = if s.weight == 1 { sum } else { format!("{} x ({})", s.weight, sum) };
+            out += &format!("  wire {}: {} = {}\n", wire + 1, weighted, i.steps[wire]);
+        }
+        out += &format!("  steps: {} + {} = {}\n", i.steps[0], i.steps[1], i.cost());
+    }
+    out
+}
+
+// the order --list prints intersections in
+fn list_order(i: &Intersection, origin: Point, metric: Metric) -> (i64, Steps) {
+    (metric.measure(i.point, origin), i.cost())
+}
+
+// one row per distinct intersection other than a shared start with steps in lo..=hi, distances
+// from reference by metric, the part 1 and part 2 winners over every intersection starred, then the
+// 1-based move of each wire that gets there and how they meet
+fn intersection_table(intersections: &[Intersection], start: Option<Point>, reference: Point, metric: Metric, (lo, hi): (i32, i32)) -> String {
+    let mut rows = distinct_points(intersections, start);
+    rows.sort_by_key(|i| list_order(i, reference, metric));
+    let best_distance = rows.iter().map(|i| metric.measure(i.point, reference)).min();
+    let best_steps = rows.iter().map(|i| i.cost().0 as i64).min();
+    rows.retain(|i| between(i.cost().0, lo, hi));
+    let star = |value: i64, best: Option<i64>| format!("{}{}", value, if Some(value) == best { "*" } else { " " });
+    let cells: Vec<(String, String, String, String, CrossingKind)> = rows.iter()
+        .map(|i| (
+            i.point.to_string(),
+            star(metric.measure(i.point, reference), best_distance),
+            star(i.cost().0 as i64, best_steps),
+            format!("{}/{}", i.move_index[0] + 1, i.move_index[1] + 1),
+            i.kind,
+        ))
+        .collect();
+    let pw = cells.iter().map(|c| c.0.len()).chain(Some("point".len())).max().unwrap_or(0);
+    let dw = cells.iter().map(|c| c.1.len()).chain(Some("distance ".len())).max().unwrap_or(0);
+    let sw = cells.iter().map(|c| c.2.len()).chain(Some("steps ".len())).max().unwrap_or(0);
+    let mw = cells.iter().map(|c| c.3.len()).chain(Some("moves".len())).max().unwrap_or(0);
+    let mut out = format!("{:<pw$}  {:>dw$}  {:>sw$}  {:<mw$}  kind\n", "point", "distance ", "steps ", "moves", pw = pw, dw = dw, sw = sw, mw = mw);
+    for (point, dist, steps, moves, kind) in cells {
+        out += &format!("{:<pw$}  {:>dw$}  {:>sw$}  {:<mw$}  {}\n", point, dist, steps, moves, kind, pw = pw, dw = dw, sw = sw, mw = mw);
+    }
+    out
+}
+
+// tokens are the moves as written, see WireHeader
+fn solve_command(args: &[String], paths: [&[PathSegment]; 2], tokens: [&[String]; 2], syntax: Syntax, segments_0: Vec<Segment>, segments_1: Vec<Segment>, reference: Point) {
+    let color = color_enabled();
+    let metric = match (arg_value(args, "--metric"), wrap_size(args)) {
+        (Some(_), Some(_)) => fail("--metric does not work with --wrap"),
+        (None, Some((width, height))) => Metric::Toroidal { width, height },
+        (m, None) => m.map_or(Metric::Manhattan, |m| Metric::parse(&m).unwrap_or_else(|| fail(format!("unknown metric {}", m)))),
+    };
+    if args.iter().any(|a| a == "--coverage") {
+        let coverage_0 = coverage(&segments_0);
+        let coverage_1 = coverage(&segments_1);
+        let shared = shared_coverage(&coverage_0, &coverage_1);
+        println!("{} wire 1 {} cells, wire 2 {} cells, shared {} cells", styled("Coverage:", "1", color), coverage_0.len(), coverage_1.len(), shared.len());
+    }
+    if !wires_intersect(&segments_0, &segments_1) {
+        println!("{} no intersection", styled("Result:", "1", color));
+        if let Some((p, q, d)) = closest_approach(&segments_0, &segments_1) {
+            println!("{} the wires come within {} of each other, at {} and {}", styled("Hint:", "1", color), d, p, q);
+        }
+        return;
+    }
+    if args.iter().any(|a| a == "--list") {
+        let bound = |flag: &str, unbounded: i32| arg_value(args, flag)
+            .map_or(unbounded, |b| b.parse().unwrap_or_else(|_| fail(format!("invalid step bound {}", b))));
+        let steps = (bound("--min-steps", i32::MIN), bound("--max-steps", i32::MAX));
+        let overlap = match arg_value(args, "--overlap-points").as_deref() {
+            None | Some("ends") => OverlapPoints::Ends,
+            Some("every") => OverlapPoints::Every,
+            Some(other) => fail(format!("unknown overlap points {}", other)),
+        };
+        let table = intersection_table(&intersections(&segments_0, &segments_1, paths, reference, overlap), shared_start(&segments_0, &segments_1), reference, metric, steps);
+        print!("{}", table);
+        if steps != (i32::MIN, i32::MAX) {
+            println!("{} intersections with steps in range", table.lines().count() - 1);
+        }
+    }
+    if args.iter().any(|a| a == "--fingerprint") {
+        println!("{} wire 1 {:016x}, wire 2 {:016x}", styled("Fingerprint:", "1", color), fingerprint(&segments_0), fingerprint(&segments_1));
+    }
+    if let Some(r) = arg_value(args, "--within") {
+        let radius: i32 = r.parse().unwrap_or_else(|_| fail(format!("invalid radius {}", r)));
+        for found in intersections_within(&segments_0, &segments_1, reference, radius) {
+            if let Some(i) = intersection_at(&segments_0, &segments_1, paths, reference, found.point) {
+                println!("{} distance {} steps {}", i.point, i.distance, i.cost());
+            }
+        }
+    }
+    if let Some(w) = arg_value(args, "--wall") {
+        let wall = parse_rect(&w).filter(|r| r.min.x() == r.max.x() || r.min.y() == r.max.y())
+            .unwrap_or_else(|| fail(format!("invalid wall {}, it must be a horizontal or vertical line", w)));
+        let line = Segment { end1: wall.min, end2: wall.max, steps: 0, start: wall.min, length: distance(wall.min, wall.max), mirrored: false, weight: 1 };
+        for (n, segments) in [&segments_0, &segments_1].iter().enumerate() {
+            let points: Vec<String> = intersections_with_line(segments, line).iter().map(|p| p.to_string()).collect();
+            let label = if n == 0 { styled("Wall:", "1", color) } else { "     ".to_string() };
+            println!("{} wire {}: {}", label, n + 1, if points.is_empty() { "none".to_string() } else { points.join(" ") });
+        }
+    }
+    if let Some(d) = arg_value(args, "--beyond") {
+        let limit: i64 = d.parse().unwrap_or_else(|_| fail(format!("invalid distance {}", d)));
+        // nearest first, so the search stops at the first one past the limit
+        match intersections_by_metric(&segments_0, &segments_1, reference, metric).find(|i| metric.measure(i.point, reference) > limit) {
+            Some(i) => println!("{} {} at {}", styled("Beyond:", "1", color), metric.measure(i.point, reference), i.point),
+            None => println!("{} nothing farther than {}", styled("Beyond:", "1", color), limit),
+        }
+    }
+    if let Some(r) = arg_value(args, "--rect") {
+        let rect = parse_rect(&r).unwrap_or_else(|| fail(format!("invalid rect {}", r)));
+        for found in intersections_in_rect(&segments_0, &segments_1, reference, rect) {
+            if let Some(i) = intersection_at(&segments_0, &segments_1, paths, reference, found.point) {
+                println!("{} distance {} steps {}", i.point, i.distance, i.cost());
+            }
+        }
+    }
+    if let Some(b) = arg_value(args, "--band") {
+        let band: Vec<i32> = b.split(',').map(|v| v.trim().parse().ok()).collect::<Option<_>>()
+            .unwrap_or_else(|| fail(format!("invalid band {}", b)));
+        let (lo, hi) = match band[..] {
+            [lo, hi] => (lo, hi),
+            _ => fail(format!("invalid band {}", b)),
+        };
+        let count = count_in_band(&segments_0, &segments_1, reference, lo, hi);
+        println!("{} {} intersections {} to {} from {}", styled("Band:", "1", color), count, lo, hi, reference);
+    }
+    if args.iter().any(|a| a == "--all-metrics") {
+        let all = intersections(&segments_0, &segments_1, paths, reference, OverlapPoints::Ends);
+        let best = best_per_metric(&all, reference, shared_start(&segments_0, &segments_1));
+        if let (Some(m), Some(s), Some(e)) = (best.manhattan, best.steps, best.euclidean) {
+            println!("{} manhattan {} at {}", styled("Metrics:", "1", color), m.distance, m.point);
+            println!("         steps {} at {}", s.cost(), s.point);
+            println!("         euclidean {:.3} at {}", (squared_distance(e.point, reference) as f64).sqrt(), e.point);
+        }
+    }
+    if let Some(name) = arg_value(args, "--objective") {
+        let objective = Objective::parse(&name).unwrap_or_else(|| fail(format!("unknown objective {}", name)));
+        if let Some((p, score)) = best_by(objective, &segments_0, &segments_1, reference) {
+            println!("{} {} {} at {}", styled("Objective:", "1", color), name, score, p);
+        }
+    }
+    let segments = [segments_0.clone(), segments_1.clone()];
+    let exclude_radius = arg_value(args, "--exclude-radius")
+        .map_or(0, |r| r.parse().unwrap_or_else(|_| fail(format!("invalid radius {}", r))));
+    // redrawn in place on stderr, so it stays out of the answers on stdout
+    let mut draw_progress = |done: usize, total: usize| {
+        eprint!("\rsearching: {}/{} segments", done, total);
+        if done == total {
+            eprintln!();
+        }
+    };
+    let progress: Option<&mut dyn FnMut(usize, usize)> = if args.iter().any(|a| a == "--progress") { Some(&mut draw_progress) } else { None };
+    let (part1, by_metric, by_steps) = match closest_intersect(segments_0, segments_1, reference, metric, exclude_radius, &obstacles(args), progress) {
+        Some(found) => found,
+        None if exclude_radius > 0 => {
+            println!("{} no intersection farther than {} from the start", styled("Result:", "1", color), exclude_radius);
+            return;
+        }
+        None => {
+            println!("{} no intersection", styled("Result:", "1", color));
+            return;
+        }
+    };
+    let winner = |p: Point| intersection_at(&segments[0], &segments[1], paths, reference, p).expect("a winner is on both wires");
+    let (by_metric, by_steps) = (winner(by_metric.point), winner(by_steps.point));
+    print!("{}", solve_summary(paths, [&segments[0], &segments[1]], (part1, by_metric.point), (by_steps.cost().0, by_steps.point), color));
+    if args.iter().any(|a| a == "--explain") {
+        let wires = Explanation { segments: [&segments[0], &segments[1]], moves: paths, tokens, syntax, reference, metric };
+        print!("{}", explain(&wires, &by_metric, &by_steps));
+    }
+}
+
+// a canvas dimension given by flag, which must be a positive number
+fn size_arg(args: &[String], flag: &str) -> std::result::Result<Option<usize>, Error> {
+    arg_value(args, flag)
+        .map(|v| v.parse().ok().filter(|n| *n > 0).ok_or(Error::Usage { message: format!("invalid {} {}", flag, v) }))
+        .transpose()
+}
+
+fn render_command(args: &[String], segments_0: Vec<Segment>, segments_1: Vec<Segment>) -> std::result::Result<(), Error> {
+    let format = arg_value(args, "--format").unwrap_or_else(|| "braille".to_string());
+    // a frame of the wires being traced: only their first N steps, so only crossings both reached
+    let (segments_0, segments_1) = match arg_value(args, "--steps") {
+        Some(n) => {
+            let steps = n.parse().map_err(|_| Error::Usage { message: format!("invalid step count {}", n) })?;
+            (prefix(&segments_0, steps), prefix(&segments_1, steps))
+        }
+        None => (segments_0, segments_1),
+    };
+    let start = shared_start(&segments_0, &segments_1);
+    let intersections: Vec<PointWithCost> = all_intersections(&segments_0, &segments_1).into_iter()
+        .filter(|i| Some(i.point) != start)
+        .collect();
+    let width = size_arg(args, "--width")?.unwrap_or_else(render::terminal_width);
+    let height = size_arg(args, "--height")?;
+    match format.as_str() {
+        "braille" => {
+            let points: Vec<Point> = intersections.iter().map(|i| i.point).collect();
+            match (arg_value(args, "--window"), arg_value(args, "--focus")) {
+                (Some(w), _) => {
+                    let window = parse_rect(&w).ok_or(Error::Usage { message: format!("invalid window {}", w) })?;
+                    print!("{}", render::braille_window(&segments_0, &segments_1, &points, window, width, height));
+                }
+                (None, Some(f)) => {
+                    let v: Vec<i32> = f.split(',').filter_map(|n| n.parse().ok()).collect();
+                    let (w, h) = match v[..] {
+                        [w, h] if w > 0 && h > 0 => (w, h),
+                        _ => return Err(Error::Usage { message: format!("invalid focus size {}", f) }),
+                    };
+                    // where most of the wiring is, which may be far from the origin
+                    let center = centroid(&[&segments_0[..], &segments_1[..]].concat());
+                    let min = Point::new(center.x() - w / 2, center.y() - h / 2);
+                    let window = Rect { min, max: Point::new(min.x() + w, min.y() + h) };
+                    print!("{}", render::braille_window(&segments_0, &segments_1, &points, window, width, height));
+                }
+                (None, None) => print!("{}", render::braille(&segments_0, &segments_1, &points, width, height)),
+            }
+        }
+        "heatmap" => {
+            let color = !args.iter().any(|a| a == "--no-color");
+            print!("{}", render::heatmap(&segments_0, &segments_1, &intersections, width, height, color));
+        }
+        "pgm" => {
+            let bins = size_arg(args, "--bins")?.unwrap_or(512);
+            let image = render::pgm(&segments_0, &segments_1, bins);
+            match arg_value(args, "-o") {
+                Some(path) => std::fs::write(&path, image).map_err(|source| Error::Io { path, source })?,
+                None => std::io::stdout().write_all(&image).map_err(|source| Error::Io { path: "stdout".to_string(), source })?,
+            }
+        }
+        _ => return Err(Error::Usage { message: format!("unknown format {}", format) }),
+    }
+    Ok(())
+}
+
+fn path_length(path: &[PathSegment]) -> i32 {
+    path.iter().map(|m| m.distance).sum()
+}
+
+// bench: count_intersections timed against enumerating every crossing with all_intersections, the
+// mean over --runs of each; both count every point of an overlap so the totals must agree
+fn bench_command(args: &[String], segments_0: &[Segment], segments_1: &[Segment]) {
+    let runs: u32 = arg_value(args, "--runs")
+        .map_or(10, |r| r.parse().ok().filter(|r| *r > 0).unwrap_or_else(|| fail(format!("invalid run count {}", r))));
+    let start = shared_start(segments_0, segments_1);
+    let time = |f: &dyn Fn() -> usize| -> (usize, f64) {
+        let started = Instant::now();
+        let mut found = 0;
+        for _ in 0..runs {
+            found = std::hint::black_box(f());
+        }
+        (found, started.elapsed().as_secs_f64() * 1000.0 / runs as f64)
+    };
+    let (enumerated, enumerate_ms) = time(&|| distinct_except(&all_intersections_with(segments_0, segments_1, OverlapPoints::Every), start).len());
+    let (counted, count_ms) = time(&|| count_intersections(segments_0, segments_1, OverlapCount::PerPoint));
+    println!("{} + {} segments, mean of {} runs", segments_0.len(), segments_1.len(), runs);
+    println!("all_intersections:   {:>8} crossings in {:.3} ms", enumerated, enumerate_ms);
+    println!("count_intersections: {:>8} crossings in {:.3} ms", counted, count_ms);
+    if counted != enumerated {
+        fail("the counts disagree");
+    }
+}
+
+// a wire as an input line, its start written out unless it is (0,0)
+fn wire_line(wire: &Wire, weight: i32) -> String {
+    let start = if wire.origin == Point::ORIGIN { String::new() } else { format!("{}: ", wire.origin) };
+    let weight = if weight == 1 { String::new() } else { format!("x{}:", weight) };
+    let mut moves: Vec<String> = wire.moves.iter().map(|m| format!("{}{}", m.direction, m.distance)).collect();
+    if moves.is_empty() {
+        // a line needs a move to parse
+        moves.push("R0".to_string());
+    }
+    format!("{}{}{}", start, weight, moves.join(","))
+}
+
+// both wires rewritten as input lines, --wire N picking the only one changed
+fn transform_command(args: &[String], wires: [Wire; 2], weights: [i32; 2], options: ParseOptions) {
+    let only = arg_value(args, "--wire").map(|n| match n.as_str() {
+        "1" => 0,
+        "2" => 1,
+        _ => fail(format!("invalid wire {}, expected 1 or 2", n)),
+    });
+    let slice = arg_value(args, "--slice").map(|s| {
+        let v: Vec<i32> = s.split(',').filter_map(|n| n.parse().ok()).collect();
+        match v[..] {
+            [from, to] => (from, to),
+            _ => fail(format!("invalid slice {}", s)),
+        }
+    });
+    let rotate: Option<i32> = arg_value(args, "--rotate")
+        .map(|k| k.parse().unwrap_or_else(|_| fail(format!("invalid quarter turns {}", k))));
+    let translate = arg_value(args, "--translate").map(|t| {
+        let v: Vec<i32> = t.split(',').filter_map(|n| n.parse().ok()).collect();
+        match v[..] {
+            [dx, dy] => (dx, dy),
+            _ => fail(format!("invalid translation {}", t)),
+        }
+    });
+    // its start headers do not matter, only the moves are walked on
+    let tails = arg_value(args, "--append").map(|f| {
+        let (tail_0, tail_1) = read_wires_with(&f, options, None).unwrap_or_else(|e| fail(e));
+        [Wire::new(tail_0, Point::ORIGIN), Wire::new(tail_1, Point::ORIGIN)]
+    });
+    for (n, wire) in wires.iter().enumerate() {
+        let mut wire = wire.clone();
+        if only.is_none_or(|w| w == n) {
+            if let Some((from, to)) = slice {
+                wire = wire.slice_steps(from, to).unwrap_or_else(|e| fail(format!("wire {}: {}", n + 1, e)));
+            }
+            if args.iter().any(|a| a == "--reverse") {
+                wire = wire.reversed();
+            }
+            if let Some(tails) = &tails {
+                wire = wire.concat(&tails[n]);
+            }
+            if let Some(k) = rotate {
+                wire = wire.rotated90(k);
+            }
+            match arg_value(args, "--mirror").as_deref() {
+                None => {}
+                Some("x") => wire = wire.mirrored_x(),
+                Some("y") => wire = wire.mirrored_y(),
+                Some(other) => fail(format!("unknown mirror axis {}", other)),
+            }
+            if let Some((dx, dy)) = translate {
+                wire = wire.translated(dx, dy);
+            }
+        }
+        println!("{}", wire_line(&wire, weights[n]));
+    }
+}
+
+// one line of an edit script; wires and their moves are numbered from 1
+fn edit_step(session: &mut Session, line: &str, options: ParseOptions) -> std::result::Result<(), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let wire = |w: &str| match w {
+        "1" => Ok(0),
+        "2" => Ok(1),
+        _ => Err(format!("invalid wire {}, expected 1 or 2", w)),
+    };
+    let step = |m: &str| parse_move(m, 0, options).map_err(|e| e.message);
+    let edit = match words[..] {
+        ["append", w, m] => Edit::Append(wire(w)?, step(m)?),
+        ["pop", w] => Edit::Pop(wire(w)?),
+        ["replace", w, i, m] => {
+            let index = i.parse::<usize>().ok().filter(|i| *i > 0).ok_or(format!("invalid move number {}", i))?;
+            Edit::Replace(wire(w)?, index - 1, step(m)?)
+        }
+        ["undo"] => return if session.undo() { Ok(()) } else { Err("nothing to undo".to_string()) },
+        ["redo"] => return if session.redo() { Ok(()) } else { Err("nothing to redo".to_string()) },
+        _ => return Err(format!("unknown edit {}", line)),
+    };
+    session.apply(edit).map_err(|e| e.to_string())
+}
+
+// edit: the input changed by one script line at a time, both answers printed after each without
+// solving again from scratch
+fn edit_command(args: &[String], paths: [&[PathSegment]; 2], origin: Point, options: ParseOptions) {
+    let script: Box<dyn BufRead> = match arg_value(args, "--script") {
+        Some(path) => Box::new(BufReader::new(File::open(&path).unwrap_or_else(|e| fail(format!("{}: {}", path, e))))),
+        None => Box::new(BufReader::new(std::io::stdin())),
+    };
+    let mut session = Session::from_moves(origin, paths);
+    for line in script.lines() {
+        let line = line.unwrap_or_else(|e| fail(e));
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match edit_step(&mut session, line, options) {
+            Ok(()) => match session.solution() {
+                Some(s) => println!("{}: distance {} at {}, steps {} at {}", line, s.distance, s.distance_at, s.steps, s.steps_at),
+                None => println!("{}: no intersection", line),
+            },
+            Err(e) => println!("{}: {}", line, e),
+        }
+    }
+}
+
+fn nearest_command(args: &[String], paths: [&[PathSegment]; 2], starts: [Point; 2]) {
+    let q = match arg_value(args, "--point") {
+        Some(q) => parse_point(&q).unwrap_or_else(|| fail(format!("invalid point {}", q))),
+        None => fail("nearest needs --point X,Y"),
+    };
+    for (n, path) in paths.iter().enumerate() {
+        let (p, d, steps) = Wire::new(path.to_vec(), starts[n]).nearest_point(q);
+        println!("wire {}: {} is {} from {}, {} steps along the wire", n + 1, p, d, q, steps);
+    }
+}
+
+// the merged segments of both wires in an input file
+fn load_segments(path: &str, options: ParseOptions, origin: Point, max_segments: Option<usize>) -> std::result::Result<(Vec<Segment>, Vec<Segment>), Error> {
+    let ((path_0, path_1), headers) = read_wire_headers(path, options, None)?;
+    let segments_0 = wire_segments(path_0, &headers[0], origin);
+    let segments_1 = wire_segments(path_1, &headers[1], origin);
+    check_segment_limit(path, [&segments_0, &segments_1], max_segments)?;
+    Ok((segments_0, segments_1))
+}
+
+// both answers for one input file, None when its wires never cross, and how many crossings it has
+fn solve_file(path: &str, options: ParseOptions, origin: Point, reference: Point, max_segments: Option<usize>, policy: OverlapCount) -> std::result::Result<(Option<Solution>, usize), Error> {
+    let (segments_0, segments_1) = load_segments(path, options, origin, max_segments)?;
+    let count = count_intersections(&segments_0, &segments_1, policy);
+    Ok((solve(segments_0, segments_1, reference), count))
+}
+
+// --batch: one input file per line of the manifest, relative to it; blank lines and lines starting
+// with # are skipped. A file that fails gets its error in its row and the rest still run
+fn batch_command(manifest: &str, options: ParseOptions, origin: Point, reference: Point, max_segments: Option<usize>, policy: OverlapCount) {
+    let lines = std::fs::read_to_string(manifest).unwrap_or_else(|e| fail(format!("{}: {}", manifest, e)));
+    let dir = Path::new(manifest).parent().unwrap_or_else(|| Path::new(""));
+    let mut rows: Vec<(String, String, String, String)> = vec!();
+    for line in lines.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let path = dir.join(line).to_string_lossy().into_owned();
+        rows.push(match solve_file(&path, options, origin, reference, max_segments, policy) {
+            Ok((Some(solution), count)) => (line.to_string(), solution.distance.to_string(), solution.steps.to_string(), count.to_string()),
+            Ok((None, count)) => (line.to_string(), "-".to_string(), "-".to_string(), count.to_string()),
+            Err(e) => (line.to_string(), format!("error: {}", e), String::new(), String::new()),
+        });
+    }
+    let fw = rows.iter().map(|r| r.0.len()).chain(Some("file".len())).max().unwrap_or(0);
+    let dw = rows.iter().filter(|r| !r.2.is_empty()).map(|r| r.1.len()).chain(Some("distance".len())).max().unwrap_or(0);
+    let sw = rows.iter().map(|r| r.2.len()).chain(Some("steps".len())).max().unwrap_or(0);
+    let cw = rows.iter().map(|r| r.3.len()).chain(Some("crossings".len())).max().unwrap_or(0);
+    println!("{:<fw$}  {:>dw$}  {:>sw$}  {:>cw$}", "file", "distance", "steps", "crossings", fw = fw, dw = dw, sw = sw, cw = cw);
+    for (file, distance, steps, count) in rows {
+        if steps.is_empty() {
+            println!("{:<fw$}  {}", file, distance, fw = fw);
+        } else {
+            println!("{:<fw$}  {:>dw$}  {:>sw$}  {:>cw$}", file, distance, steps, count, fw = fw, dw = dw, sw = sw, cw = cw);
+        }
+    }
+}
+
+// --compare OLD NEW: how the answers, the crossing points and each wire changed between two inputs
+fn compare_command(old: &str, new: &str, options: ParseOptions, origin: Point, reference: Point, max_segments: Option<usize>) {
+    let points = |path: &str| -> (Option<Solution>, Vec<Point>) {
+        let (segments_0, segments_1) = load_segments(path, options, origin, max_segments).unwrap_or_else(|e| fail(e));
+        let mut points: Vec<Point> = distinct_except(&all_intersections(&segments_0, &segments_1), shared_start(&segments_0, &segments_1)).iter()
+            .map(|i| i.point)
+            .collect();
+        points.sort_unstable_by_key(|p| (distance(*p, reference), p.x(), p.y()));
+        let solution = solve(segments_0, segments_1, reference);
+        (solution, points)
+    };
+    let (old_solution, old_points) = points(old);
+    let (new_solution, new_points) = points(new);
+    match (old_solution, new_solution) {
+        (Some(a), Some(b)) => {
+            let changes = diff_solutions(&a, &b);
+            if changes.is_empty() {
+                println!("answers unchanged: distance {}, steps {}", a.distance, a.steps);
+            }
+            for change in changes {
+                println!("{}", change);
+            }
+        }
+        (Some(_), None) => println!("the wires no longer cross"),
+        (None, Some(b)) => println!("the wires now cross: distance {}, steps {}", b.distance, b.steps),
+        (None, None) => println!("the wires cross in neither input"),
+    }
+    let (old_set, new_set): (HashSet<Point>, HashSet<Point>) = (old_points.iter().copied().collect(), new_points.iter().copied().collect());
+    let appeared: Vec<String> = new_points.iter().filter(|p| !old_set.contains(p)).map(|p| p.to_string()).collect();
+    let disappeared: Vec<String> = old_points.iter().filter(|p| !new_set.contains(p)).map(|p| p.to_string()).collect();
+    println!("{} new crossings{}{}", appeared.len(), if appeared.is_empty() { "" } else { ": " }, appeared.join(" "));
+    println!("{} removed crossings{}{}", disappeared.len(), if disappeared.is_empty() { "" } else { ": " }, disappeared.join(" "));
+    let wires = |path: &str| -> [Wire; 2] {
+        let ((path_0, path_1), headers) = read_wire_headers(path, options, None).unwrap_or_else(|e| fail(e));
+        [Wire::new(path_0, headers[0].start.unwrap_or(origin)), Wire::new(path_1, headers[1].start.unwrap_or(origin))]
+    };
+    let (old_wires, new_wires) = (wires(old), wires(new));
+    for (n, (a, b)) in old_wires.iter().zip(new_wires.iter()).enumerate() {
+        let change = if a.moves == b.moves && a.origin == b.origin {
+            "unchanged"
+        } else if a.same_geometry(b) {
+            "same path, its moves split differently"
+        } else if a.same_geometry_ignoring_order(b) {
+            "same lines, walked in another order"
+        } else {
+            "changed"
+        };
+        println!("wire {}: {}", n + 1, change);
+    }
+}
+
+fn route_command(args: &[String], segments: [&[Segment]; 2]) {
+    let point = |flag: &str| match arg_value(args, flag) {
+        Some(p) => parse_point(&p).unwrap_or_else(|| fail(format!("invalid point {}", p))),
+        None => fail("route needs --from X,Y and --to X,Y"),
+    };
+    let (from, to) = (point("--from"), point("--to"));
+    match route::route(segments, from, to) {
+        Some((length, path)) => {
+            let nodes: Vec<String> = path.iter().map(|p| p.to_string()).collect();
+            println!("{} along the wires: {}", length, nodes.join(" -> "));
+        }
+        None => println!("no route from {} to {} along the wires", from, to),
+    }
+}
+
+// re-derives the part 2 answer from each wire on its own: the steps each needs to reach the winner
+// must add up to the answer, and walking that many steps must land on it. Weights are ignored
+fn check_steps(wires: &[Wire; 2], reference: Point) -> std::result::Result<Option<Steps>, String> {
+    let solution = match solve(wires[0].segments.clone(), wires[1].segments.clone(), reference) {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    let p = solution.steps_at;
+    let mut total = 0;
+    for (n, wire) in wires.iter().enumerate() {
+        let steps = wire.steps_to(p).ok_or_else(|| format!("wire {} never reaches {}", n + 1, p))?;
+        if wire.point_at_step(steps) != Some(p) {
+            return Err(format!("wire {} is not at {} after {} steps", n + 1, p, steps));
+        }
+        total += steps;
+    }
+    if Steps(total) != solution.steps {
+        return Err(format!("steps to {} add up to {}, not {}", p, total, solution.steps));
+    }
+    Ok(Some(solution.steps))
+}
+
+fn stats_command(args: &[String], paths: [&[PathSegment]; 2], starts: [Point; 2], reference: Point) {
+    let wires = [Wire::new(paths[0].to_vec(), starts[0]), Wire::new(paths[1].to_vec(), starts[1])];
+    let stats = stats::stats(&wires, reference, args.iter().any(|a| a == "--regions"));
+    match arg_value(args, "--output").as_deref() {
+        None | Some("text") => print!("{}", stats::text(&stats)),
+        Some("json") => println!("{}", stats::json(&stats)),
+        Some(other) => fail(format!("unknown output {}", other))
+    }
+}
+
+// --random: two generated wires of --count moves each, the same ones for the same --seed
+fn random_wires(args: &[String]) -> Option<(Vec<PathSegment>, Vec<PathSegment>)> {
+    if !args.iter().any(|a| a == "--random") {
+        return None;
+    }
+    let seed = arg_value(args, "--seed").map_or(0, |s| s.parse().unwrap_or_else(|_| fail(format!("invalid seed {}", s))));
+    let count = arg_value(args, "--count").map_or(301, |c| c.parse().unwrap_or_else(|_| fail(format!("invalid count {}", c))));
+    let mut rng = random::Rng::new(seed);
+    let wire1 = random::random_wire(&mut rng, count);
+    let wire2 = random::random_wire(&mut rng, count);
+    Some((wire1, wire2))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return;
+    }
+    let command = match args.get(1) {
+        Some(command) if !command.starts_with('-') => command.as_str(),
+        // plain --format used to render without a command
+        _ if arg_value(&args, "--format").is_some_and(|f| f != "bin") => "render",
+        _ => "solve"
+    };
+    let syntax = match arg_value(&args, "--syntax").as_deref() {
+        None | Some("aoc") => Syntax::Aoc,
+        Some("xy") => Syntax::Xy,
+        Some(other) => fail(format!("unknown syntax {}", other))
+    };
+    let options = ParseOptions {
+        signed_distance: args.iter().any(|a| a == "--signed-distance"),
+        binary: command != "render" && arg_value(&args, "--format").as_deref() == Some("bin"),
+        ..ParseOptions::new(syntax)
+    };
+    let origin = match arg_value(&args, "--start") {
+        Some(o) => parse_point(&o).unwrap_or_else(|| fail(format!("invalid start {}", o))),
+        None => Point::ORIGIN
+    };
+    // where part 1 distances are measured from; the wires still start at origin
+    let reference = match arg_value(&args, "--origin") {
+        Some(o) => parse_point(&o).unwrap_or_else(|| fail(format!("invalid origin {}", o))),
+        None => origin
+    };
+    let max_segments = arg_value(&args, "--max-segments")
+        .map(|n| n.parse().unwrap_or_else(|_| fail(format!("invalid segment limit {}", n))));
+    VERBOSE.store(args.iter().any(|a| a == "--verbose"), Ordering::Relaxed);
+    let input = input_path(&args);
+    let pair_delimiter = arg_value(&args, "--pair-delimiter");
+    if let Some(target) = arg_value(&args, "--emit-bin") {
+        let ((path_0, path_1), headers) = read_wire_headers(&input, options, pair_delimiter.as_deref()).unwrap_or_else(|e| fail(e));
+        // the compact form has no room for a start or a weight, so drop neither silently
+        if headers.iter().any(|h| h.start.is_some() || h.weight != 1) {
+            fail(format!("{}: --emit-bin cannot keep wire starts or weights", input));
+        }
+        let out = File::create(&target).unwrap_or_else(|e| fail(format!("{}: {}", target, e)));
+        compact::write(&mut std::io::BufWriter::new(out), [&path_0, &path_1]).unwrap_or_else(|e| fail(format!("{}: {}", target, e)));
+        println!("wrote {}", target);
+        return;
+    }
+    if let Some(at) = args.iter().position(|a| a == "--compare") {
+        match (args.get(at + 1), args.get(at + 2)) {
+            (Some(old), Some(new)) => compare_command(old, new, options, origin, reference, max_segments),
+            _ => fail("--compare needs two input files"),
+        }
+        return;
+    }
+    if let Some(manifest) = arg_value(&args, "--batch") {
+        if command != "solve" {
+            fail("--batch only works with solve");
+        }
+        let policy = match arg_value(&args, "--overlap-count").as_deref() {
+            None | Some("run") => OverlapCount::PerRun,
+            Some("point") => OverlapCount::PerPoint,
+            Some(other) => fail(format!("unknown overlap count {}", other)),
+        };
+        batch_command(&manifest, options, origin, reference, max_segments, policy);
+        return;
+    }
+    match command {
+        "edit" => {
+            let ((path_0, path_1), headers) = read_wire_headers(&input, options, pair_delimiter.as_deref()).unwrap_or_else(|e| fail(e));
+            if headers.iter().any(|h| h.start.is_some_and(|s| s != origin) || h.weight != 1) {
+                fail(format!("{}: edit needs both wires to start together and cost 1 per step", input));
+            }
+            edit_command(&args, [&path_0, &path_1], origin, options);
+        }
+        "transform" => {
+            let ((path_0, path_1), headers) = read_wire_headers(&input, options, pair_delimiter.as_deref()).unwrap_or_else(|e| fail(e));
+            let wires = [Wire::new(path_0, headers[0].start.unwrap_or(origin)), Wire::new(path_1, headers[1].start.unwrap_or(origin))];
+            transform_command(&args, wires, [headers[0].weight, headers[1].weight], options);
+        }
+        "validate" => {
+            let ((path_0, path_1), headers) = read_wire_headers(&input, options, pair_delimiter.as_deref()).unwrap_or_else(|e| fail(e));
+            let wires = [Wire::new(path_0, headers[0].start.unwrap_or(origin)), Wire::new(path_1, headers[1].start.unwrap_or(origin))];
+            match check_steps(&wires, reference) {
+                Ok(Some(steps)) => println!("{}: ok, {} and {} moves, part 2 ({}) checked", input, wires[0].moves.len(), wires[1].moves.len(), steps),
+                Ok(None) => println!("{}: ok, {} and {} moves, no intersection", input, wires[0].moves.len(), wires[1].moves.len()),
+                Err(e) => fail(format!("{}: {}", input, e)),
+            }
+        }
+        "solve" | "render" | "stats" | "nearest" | "route" | "graph" | "bench" => {
+            let ((path_0, path_1), headers) = match random_wires(&args) {
+                Some(wires) => (wires, [NO_HEADER; 2]),
+                None => read_wire_headers(&input, options, pair_delimiter.as_deref()).unwrap_or_else(|e| fail(e)),
+            };
+            let starts = [headers[0].start.unwrap_or(origin), headers[1].start.unwrap_or(origin)];
+            let (segments_0, segments_1) = match wrap_size(&args) {
+                Some(size) => (weighted(path_to_wrapped_segments(path_0.clone(), starts[0], size), headers[0].weight),
+                               weighted(path_to_wrapped_segments(path_1.clone(), starts[1], size), headers[1].weight)),
+                None => (wire_segments(path_0.clone(), &headers[0], origin), wire_segments(path_1.clone(), &headers[1], origin)),
+            };
+            check_segment_limit(&input, [&segments_0, &segments_1], max_segments).unwrap_or_else(|e| fail(e));
+            check_obstacles(&input, [(&path_0, starts[0]), (&path_1, starts[1])], &obstacles(&args), wrap_size(&args)).unwrap_or_else(|e| fail(e));
+            if fingerprint(&segments_0) == fingerprint(&segments_1) {
+                // usually the same line pasted twice; the answers then come from where the copies overlap
+                eprintln!("warning: {}: both wires trace the same path, so every shared point counts as an intersection", input);
+            }
+            // only what lies in the window is kept, every piece still counting steps from the start
+            let (segments_0, segments_1) = match arg_value(&args, "--clip") {
+                Some(c) => {
+                    let window = parse_rect(&c).unwrap_or_else(|| fail(format!("invalid clip window {}", c)));
+                    (clip_to_rect(&segments_0, window), clip_to_rect(&segments_1, window))
+                }
+                None => (segments_0, segments_1),
+            };
+            match command {
+                "solve" => {
+                    let started = Instant::now();
+                    solve_command(&args, [&path_0, &path_1], [&headers[0].tokens, &headers[1].tokens], syntax, segments_0, segments_1, reference);
+                    if args.iter().any(|a| a == "--random") {
+                        println!("Time: {:.3} ms", started.elapsed().as_secs_f64() * 1000.0);
+                    }
+                }
+                "render" => render_command(&args, segments_0, segments_1).unwrap_or_else(|e| fail(e)),
+                "nearest" => nearest_command(&args, [&path_0, &path_1], starts),
+                "route" => route_command(&args, [&segments_0, &segments_1]),
+                "graph" => print!("{}", route::wire_graph(&segments_0, &segments_1).edge_list()),
+                "bench" => bench_command(&args, &segments_0, &segments_1),
+                _ => stats_command(&args, [&path_0, &path_1], starts, reference),
+            }
+        }
+        other => fail(format!("unknown command {}\n{}", other, USAGE))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // the three examples of the puzzle with their part 1 and part 2 answers
+    pub(crate) const EXAMPLES: [(&str, &str, i32, i32); 3] = [
+        ("R8,U5,L5,D3", "U7,R6,D4,L4", 6, 30),
+        ("R75,D30,R83,U83,L12,D49,R71,U7,L72", "U62,R66,U55,R34,D71,R55,D58,R83", 159, 610),
+        ("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51", "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7", 135, 410),
+    ];
+
+    pub(crate) fn moves(line: &str) -> Vec<PathSegment> {
+        parse_path(line, ParseOptions::new(Syntax::Aoc)).unwrap()
+    }
+
+    // a wire written as an input line, built as solve builds it, from (0,0)
+    pub(crate) fn wire(line: &str) -> Vec<Segment> {
+        wire_at(line, Point::ORIGIN)
+    }
+
+    pub(crate) fn wire_at(line: &str, origin: Point) -> Vec<Segment> {
+        wire_segments(moves(line), &NO_HEADER, origin)
+    }
+
+    // two wires of count moves each, the same for the same seed
+    pub(crate) fn random_pair(seed: u64, count: usize) -> (Vec<Segment>, Vec<Segment>) {
+        let mut rng = random::Rng::new(seed);
+        let path1 = random::random_wire(&mut rng, count);
+        let path2 = random::random_wire(&mut rng, count);
+        (wire_segments(path1, &NO_HEADER, Point::ORIGIN), wire_segments(path2, &NO_HEADER, Point::ORIGIN))
+    }
+
+    fn answers(line1: &str, line2: &str) -> Option<(i32, i32)> {
+        solve(wire(line1), wire(line2), Point::ORIGIN).map(|s| (s.distance.0, s.steps.0))
+    }
+
+    #[test]
+    fn solves_the_examples() {
+        for (line1, line2, distance, steps) in EXAMPLES.iter() {
+            assert_eq!(answers(line1, line2), Some((*distance, *steps)), "{} / {}", line1, line2);
+        }
+    }
+
+    #[test]
+    fn wires_intersect_agrees_with_the_enumeration() {
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            assert!(wires_intersect(&wire(line1), &wire(line2)));
+        }
+        // only the shared start
+        assert!(!wires_intersect(&wire("R5,U5"), &wire("L5,D5")));
+        assert!(!wires_intersect(&wire("R5"), &wire("U5")));
+        // overlapping from the start, touching at an end, apart
+        assert!(wires_intersect(&wire("R5"), &wire("R2")));
+        assert!(wires_intersect(&wire("R4"), &wire_at("D2", Point::new(2, 2))));
+        assert!(!wires_intersect(&wire("R4"), &wire_at("D1", Point::new(2, 2))));
+        for seed in 0..20 {
+            let (path1, path2) = random_pair(seed, 10);
+            let any = !distinct_except(&all_intersections(&path1, &path2), shared_start(&path1, &path2)).is_empty();
+            assert_eq!(wires_intersect(&path1, &path2), any, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn no_answer_when_the_wires_never_cross() {
+        assert_eq!(answers("R5,U5", "L5,D5"), None);
+    }
+
+    #[test]
+    fn crossing_only_over_the_edge_of_a_torus() {
+        let size = (10, 10);
+        let wrapped = |line: &str| path_to_wrapped_segments(moves(line), Point::ORIGIN, size);
+        // L3 steps over the left edge to (9,0) and on to (7,0), then U5 climbs x=7
+        let path1 = wrapped("L3,U5");
+        let pieces: Vec<(Point, Point, i32, i32)> = path1.iter().map(|s| (s.start, s.end(), s.steps, s.length)).collect();
+        assert_eq!(pieces, vec!((Point::new(9, 0), Point::new(7, 0), 1, 2), (Point::new(7, 0), Point::new(7, 5), 3, 5)));
+        // U2,R8 runs along y=2 from x=0 to 8 and meets it at (7,2): 5 + 9 steps, 3 + 2 away round the torus
+        let metric = Metric::Toroidal { width: size.0, height: size.1 };
+        let (d, by_distance, by_steps) = closest_intersect(path1, wrapped("U2,R8"), Point::ORIGIN, metric, 0, &[], None).unwrap();
+        assert_eq!((d, by_distance.point, by_steps.point, by_steps.cost), (5, Point::new(7, 2), Point::new(7, 2), Steps(14)));
+        // on the plane the first wire stays at x=-3 and they never meet
+        assert!(solve(wire("L3,U5"), wire("U2,R8"), Point::ORIGIN).is_none());
+    }
+
+    #[test]
+    fn exclude_radius_just_inside_and_outside() {
+        // (3,3) is 6 from the start and 40 steps, (6,5) 11 and 30
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let excluding = |radius| closest_intersect(wire(line1), wire(line2), Point::ORIGIN, Metric::Manhattan, radius, &[], None)
+            .map(|(d, by_distance, by_steps)| (d, by_distance.point, by_steps.cost));
+        assert_eq!(excluding(0), Some((6, Point::new(3, 3), Steps(30))));
+        assert_eq!(excluding(5), excluding(0));
+        // (3,3) is skipped once it is inside
+        assert_eq!(excluding(6), Some((11, Point::new(6, 5), Steps(30))));
+        assert_eq!(excluding(10), excluding(6));
+        assert_eq!(excluding(11), None);
+        // the radius is measured from the shared start, not the reference
+        let (d, by_distance, _) = closest_intersect(wire(line1), wire(line2), Point::new(3, 3), Metric::Manhattan, 6, &[], None).unwrap();
+        assert_eq!((d, by_distance.point), (5, Point::new(6, 5)));
+    }
+
+    #[test]
+    fn progress_is_reported_without_changing_the_answers() {
+        let (path1, path2) = random_pair(7, 2500);
+        let total = path1.len();
+        let run = |progress: Option<&mut dyn FnMut(usize, usize)>| {
+            closest_intersect(path1.clone(), path2.clone(), Point::ORIGIN, Metric::Manhattan, 0, &[], progress)
+                .map(|(d, a, b)| (d, a.point, a.cost, b.point, b.cost))
+        };
+        let mut calls: Vec<(usize, usize)> = vec!();
+        let reported = run(Some(&mut |done, total| calls.push((done, total))));
+        assert_eq!(reported, run(None));
+        let mut expected: Vec<(usize, usize)> = (0..total).step_by(PROGRESS_INTERVAL).map(|done| (done, total)).collect();
+        expected.push((total, total));
+        assert_eq!(calls, expected);
+    }
+
+    #[test]
+    fn a_shifted_reference_moves_the_part_1_winner() {
+        let (line1, line2, _, steps) = EXAMPLES[0];
+        let from = |reference| {
+            let solution = solve(wire(line1), wire(line2), reference).unwrap();
+            (solution.distance_at, solution.distance.0, solution.steps.0)
+        };
+        assert_eq!(from(Point::ORIGIN), (Point::new(3, 3), 6, steps));
+        // nearer (6,5) now, part 2 unchanged
+        assert_eq!(from(Point::new(7, 7)), (Point::new(6, 5), 3, steps));
+        // 1 from the shared start, which is still no crossing
+        assert_eq!(from(Point::new(-1, 0)), (Point::new(3, 3), 7, steps));
+    }
+
+    #[test]
+    fn diff_of_two_solutions() {
+        let solution = |(line1, line2, _, _): (&str, &str, i32, i32)| solve(wire(line1), wire(line2), Point::ORIGIN).unwrap();
+        let (a, b) = (solution(EXAMPLES[1]), solution(EXAMPLES[2]));
+        assert_eq!(diff_solutions(&a, &b), vec!(
+            "distance changed 159 -> 135".to_string(),
+            format!("closest intersection moved {} -> {}", a.distance_at, b.distance_at),
+            "steps changed 610 -> 410".to_string(),
+            format!("fewest-steps intersection moved {} -> {}", a.steps_at, b.steps_at),
+        ));
+        assert!(diff_solutions(&a, &a).is_empty());
+        // the same answers at other points
+        let moved = Solution { distance_at: Point::new(0, 159), ..a };
+        assert_eq!(diff_solutions(&a, &moved), vec!(format!("closest intersection moved {} -> (0,159)", a.distance_at)));
+    }
+
+    fn kinds(path1: &[Segment], path2: &[Segment]) -> Vec<(Point, CrossingKind)> {
+        all_intersections(path1, path2).iter().map(|i| (i.point, i.kind)).collect()
+    }
+
+    #[test]
+    fn tells_crossing_kinds_apart() {
+        let across = wire("R4");
+        assert_eq!(kinds(&across, &wire_at("U4", Point::new(2, -2))), vec!((Point::new(2, 0), CrossingKind::Cross)));
+        assert_eq!(kinds(&across, &wire_at("D2", Point::new(2, 2))), vec!((Point::new(2, 0), CrossingKind::EndpointTouch)));
+        assert_eq!(kinds(&across, &wire_at("R4", Point::new(2, 0))),
+                   vec!((Point::new(2, 0), CrossingKind::Overlap), (Point::new(4, 0), CrossingKind::Overlap)));
+        assert_eq!(kinds(&wire("R2"), &wire_at("U2", Point::new(2, 0))), vec!((Point::new(2, 0), CrossingKind::CornerTouch)));
+        // the examples only cross
+        let (line1, line2, _, _) = EXAMPLES[0];
+        assert!(kinds(&wire(line1), &wire(line2)).iter().filter(|(p, _)| *p != Point::ORIGIN).all(|&(_, kind)| kind == CrossingKind::Cross));
+    }
+
+    #[test]
+    fn coverage_of_the_small_example() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let (coverage1, coverage2) = (coverage(&wire(line1)), coverage(&wire(line2)));
+        // 21 steps each and neither wire visits a cell twice
+        assert_eq!((coverage1.len(), coverage2.len()), (22, 22));
+        let shared = shared_coverage(&coverage1, &coverage2);
+        let expected: HashSet<Point> = vec!(Point::ORIGIN, Point::new(3, 3), Point::new(6, 5)).into_iter().collect();
+        assert_eq!(shared, expected);
+        // a wire walking back over itself covers each cell once
+        assert_eq!(coverage(&wire("R3,L3")).len(), 4);
+    }
+
+    #[test]
+    fn intersections_are_the_shared_cells() {
+        for seed in 0..20 {
+            let (path1, path2) = random_pair(seed, 30);
+            let found: HashSet<Point> = all_intersections_with(&path1, &path2, OverlapPoints::Every).iter().map(|i| i.point).collect();
+            assert_eq!(found, shared_coverage(&coverage(&path1), &coverage(&path2)), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn cost_is_the_steps_along_each_wire() {
+        // weights far apart, so steps counted on the wrong wire show
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            let (path1, path2) = (weighted(wire(line1), 1), weighted(wire(line2), 1000));
+            let mut cheapest: HashMap<Point, Steps> = HashMap::new();
+            for i in all_intersections(&path1, &path2) {
+                let cost = cheapest.entry(i.point).or_insert(i.cost);
+                *cost = min(*cost, i.cost);
+            }
+            for (p, cost) in cheapest {
+                let steps = steps_along(&path1, p).unwrap() + 1000 * steps_along(&path2, p).unwrap();
+                assert_eq!(cost, Steps(steps), "{} on {} / {}", p, line1, line2);
+            }
+        }
+    }
+
+    #[test]
+    fn weights_flip_the_part_2_winner() {
+        // (12,5) is 17 steps along each wire, (2,5) 37 along the first and 7 along the second
+        let (line1, line2) = ("R12,U10,L10,D10", "U5,R20");
+        let winner = |w1, w2| {
+            let solution = solve(weighted(wire(line1), w1), weighted(wire(line2), w2), Point::ORIGIN).unwrap();
+            (solution.steps_at, solution.steps)
+        };
+        assert_eq!(winner(1, 1), (Point::new(12, 5), Steps(34)));
+        assert_eq!(winner(1, 5), (Point::new(2, 5), Steps(72)));
+        assert_eq!(winner(5, 1), (Point::new(12, 5), Steps(102)));
+        // part 1 never looks at the weights
+        assert_eq!(solve(weighted(wire(line1), 1), weighted(wire(line2), 5), Point::ORIGIN).unwrap().distance, Distance(7));
+        // as read from the input line
+        assert_eq!(split_weight("x5: U5,R20").unwrap(), (5, " U5,R20", 3));
+        assert_eq!(split_weight("U5,R20").unwrap(), (1, "U5,R20", 0));
+        assert_eq!(split_weight("x0:U5").unwrap_err().message, "invalid weight 0");
+    }
+
+    #[test]
+    fn minimax_and_sum_pick_different_crossings() {
+        // (2,0) is 2 steps along the first wire and 20 along the second, (4,0) 12 along each
+        let (path1, path2) = (wire("R2,U4,R2,D4"), wire("D4,R4,U4,D3,L2,U3"));
+        assert_eq!(best_by(Objective::StepsSum, &path1, &path2, Point::ORIGIN), Some((Point::new(2, 0), 22)));
+        assert_eq!(best_by(Objective::Minimax, &path1, &path2, Point::ORIGIN), Some((Point::new(4, 0), 12)));
+        // walked towards each other along an overlap, the sum is 10 everywhere but the max is lowest half way
+        let (right, left) = (wire("R10"), wire_at("L10", Point::new(10, 0)));
+        assert_eq!(best_by(Objective::Minimax, &right, &left, Point::ORIGIN), Some((Point::new(5, 0), 5)));
+        assert_eq!(best_by(Objective::StepsSum, &right, &left, Point::ORIGIN).map(|(_, score)| score), Some(10));
+        // on the examples it lies between half the best sum and the whole of it
+        for (line1, line2, _, steps) in EXAMPLES.iter() {
+            let (_, score) = best_by(Objective::Minimax, &wire(line1), &wire(line2), Point::ORIGIN).unwrap();
+            assert!(2 * score >= *steps && score <= *steps, "{}", line1);
+        }
+    }
+
+    fn enumerated_count(path1: &[Segment], path2: &[Segment]) -> usize {
+        distinct_except(&all_intersections_with(path1, path2, OverlapPoints::Every), shared_start(path1, path2)).len()
+    }
+
+    #[test]
+    fn count_agrees_with_the_enumeration() {
+        for seed in 0..20 {
+            let (path1, path2) = random_pair(seed, 30);
+            assert_eq!(count_intersections(&path1, &path2, OverlapCount::PerPoint), enumerated_count(&path1, &path2), "seed {}", seed);
+        }
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            let (path1, path2) = (wire(line1), wire(line2));
+            assert_eq!(count_intersections(&path1, &path2, OverlapCount::PerPoint), enumerated_count(&path1, &path2));
+        }
+    }
+
+    #[test]
+    fn counts_overlaps_per_run_or_per_point() {
+        // the wires run along each other from (0,0) to (3,0) and from (3,2) to (3,5); the start is not
+        // a point but still part of the first run
+        let (path1, path2) = (wire("R8,U5,L5,D3"), wire("R3,U6,R8"));
+        assert_eq!(count_intersections(&path1, &path2, OverlapCount::PerRun), 2);
+        assert_eq!(count_intersections(&path1, &path2, OverlapCount::PerPoint), 7);
+    }
+
+    #[test]
+    fn steps_to_agrees_with_the_slow_walk() {
+        let mut pairs: Vec<(Vec<Segment>, Vec<Segment>)> = EXAMPLES.iter().map(|(line1, line2, _, _)| (wire(line1), wire(line2))).collect();
+        pairs.extend((0..5).map(|seed| random_pair(seed, 30)));
+        for (path1, path2) in pairs {
+            for i in all_intersections(&path1, &path2) {
+                assert_eq!(steps_to(&path1, i.point), steps_along(&path1, i.point), "{}", i.point);
+                assert_eq!(steps_to(&path2, i.point), steps_along(&path2, i.point), "{}", i.point);
+            }
+        }
+        // off the wire, and its start
+        let path = wire("R8,U5,L5,D3");
+        assert_eq!((steps_to(&path, Point::new(1, 1)), steps_along(&path, Point::new(1, 1))), (None, None));
+        assert_eq!(steps_along(&path, Point::ORIGIN), Some(0));
+    }
+
+    #[test]
+    fn indexing_either_wire_gives_the_same_answers() {
+        // very different sizes, so the shorter wire is indexed in one order and not the other
+        for seed in 0..10 {
+            let mut rng = random::Rng::new(seed);
+            let short = wire_segments(random::random_wire(&mut rng, 5), &NO_HEADER, Point::ORIGIN);
+            let long = wire_segments(random::random_wire(&mut rng, 200), &NO_HEADER, Point::ORIGIN);
+            for policy in [OverlapCount::PerRun, OverlapCount::PerPoint] {
+                assert_eq!(count_intersections(&short, &long, policy), count_intersections(&long, &short, policy), "seed {}", seed);
+            }
+            let (a, b) = (solve(short.clone(), long.clone(), Point::ORIGIN), solve(long, short, Point::ORIGIN));
+            assert_eq!(a.map(|s| (s.distance, s.steps)), b.map(|s| (s.distance, s.steps)), "seed {}", seed);
+        }
+    }
+
+    fn sorted_points(found: &[PointWithCost]) -> Vec<(i32, i32, Steps)> {
+        let mut points: Vec<(i32, i32, Steps)> = found.iter().map(|i| (i.point.x(), i.point.y(), i.cost)).collect();
+        points.sort();
+        points
+    }
+
+    #[test]
+    fn pruned_radius_query_matches_filtering_everything() {
+        let mut pairs: Vec<(Vec<Segment>, Vec<Segment>)> = EXAMPLES.iter().map(|(line1, line2, _, _)| (wire(line1), wire(line2))).collect();
+        pairs.extend((0..5).map(|seed| random_pair(seed, 30)));
+        for (path1, path2) in pairs {
+            let all = distinct_except(&all_intersections(&path1, &path2), shared_start(&path1, &path2));
+            for radius in [0, 6, 150, 159, 500, 5000] {
+                let filtered: Vec<PointWithCost> = all.iter().copied().filter(|i| distance(i.point, Point::ORIGIN) <= radius).collect();
+                assert_eq!(sorted_points(&intersections_within(&path1, &path2, Point::ORIGIN, radius)), sorted_points(&filtered), "radius {}", radius);
+            }
+        }
+        // (155,4) is exactly 159 away
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let within = intersections_within(&wire(line1), &wire(line2), Point::ORIGIN, 159);
+        assert_eq!(within.iter().map(|i| i.point).collect::<Vec<Point>>(), vec!(Point::new(155, 4)));
+    }
+
+    #[test]
+    fn count_in_band_is_inclusive() {
+        // example 2 crosses 159, 166, 170 and 192 away
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let (path1, path2) = (wire(line1), wire(line2));
+        let band = |lo, hi| count_in_band(&path1, &path2, Point::ORIGIN, lo, hi);
+        assert_eq!(band(160, 170), 2);
+        assert_eq!(band(159, 159), 1);
+        assert_eq!(band(159, 192), 4);
+        assert_eq!(band(171, 191), 0);
+        // the shared origin is no crossing
+        assert_eq!(band(0, 158), 0);
+        // measured from elsewhere, (155,4) and (155,11) are nearest
+        assert_eq!(count_in_band(&path1, &path2, Point::new(155, 7), 0, 4), 2);
+    }
+
+    #[test]
+    fn a_wall_across_a_zig_zag() {
+        let zig_zag = wire("U4,R2,D4,R2,U4,R2,D4");
+        let wall = |x1, y1, x2, y2| Segment::try_from(&*format!("{},{},{},{}", x1, y1, x2, y2)).unwrap();
+        let p = Point::new;
+        // from far past one side of the wire to far past the other
+        assert_eq!(intersections_with_line(&zig_zag, wall(-100, 2, 100, 2)), vec!(p(0, 2), p(2, 2), p(4, 2), p(6, 2)));
+        assert_eq!(intersections_with_line(&zig_zag, wall(1, 2, 5, 2)), vec!(p(2, 2), p(4, 2)));
+        // along the tops, each stretch on the wall gives its ends
+        assert_eq!(intersections_with_line(&zig_zag, wall(-100, 4, 100, 4)), vec!(p(0, 4), p(2, 4), p(4, 4), p(6, 4)));
+        // a vertical wall meets the bottom run once, one above the wire misses it
+        assert_eq!(intersections_with_line(&zig_zag, wall(3, -100, 3, 100)), vec!(p(3, 0)));
+        assert!(intersections_with_line(&zig_zag, wall(-100, 7, 100, 7)).is_empty());
+        assert!(intersections_with_line(&[], wall(-100, 2, 100, 2)).is_empty());
+    }
+
+    #[test]
+    fn rect_query_includes_its_edges() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let (path1, path2) = (wire(line1), wire(line2));
+        let points = |r: Rect| -> Vec<Point> { intersections_in_rect(&path1, &path2, Point::ORIGIN, r).iter().map(|i| i.point).collect() };
+        // edges through both crossings, (3,3) nearer the reference
+        assert_eq!(points(rect(3, 3, 6, 5)), vec!(Point::new(3, 3), Point::new(6, 5)));
+        assert_eq!(points(rect(4, 0, 10, 10)), vec!(Point::new(6, 5)));
+        // holding the shared start, which is never a crossing
+        assert_eq!(points(rect(-1, -1, 4, 4)), vec!(Point::new(3, 3)));
+        assert!(points(rect(-10, -10, -1, -1)).is_empty());
+        // many queries against the same prepared wires
+        let (prepared1, prepared2) = (PreparedWire::new(&path1), PreparedWire::new(&path2));
+        for r in [rect(3, 3, 6, 5), rect(4, 0, 10, 10), rect(0, 0, 3, 3)] {
+            assert_eq!(sorted_points(&prepared1.intersections_in_rect(&prepared2, Point::ORIGIN, r)),
+                       sorted_points(&intersections_in_rect(&path1, &path2, Point::ORIGIN, r)));
+        }
+    }
+
+    #[test]
+    fn both_orientations_take_the_same_path() {
+        // turned a quarter, horizontal segments become vertical and the other way round
+        let turn = |p: Point| Point::new(-p.y(), p.x());
+        let listed = |path1: &[Segment], path2: &[Segment], map: &dyn Fn(Point) -> Point| {
+            let mut found: Vec<(i32, i32, Steps, String)> = all_intersections(path1, path2).iter()
+                .map(|i| (map(i.point).x(), map(i.point).y(), i.cost, i.kind.to_string()))
+                .collect();
+            found.sort();
+            found
+        };
+        let mut lines: Vec<(&str, &str)> = EXAMPLES.iter().map(|(line1, line2, _, _)| (*line1, *line2)).collect();
+        // runs along each other horizontally and vertically
+        lines.push(("R8,U5,L5,D3", "R3,U6,R8"));
+        for (line1, line2) in lines {
+            let (wire1, wire2) = (Wire::new(moves(line1), Point::ORIGIN), Wire::new(moves(line2), Point::ORIGIN));
+            let expected = listed(&wire1.segments, &wire2.segments, &turn);
+            let (turned1, turned2) = (wire1.rotated90(1), wire2.rotated90(1));
+            assert_eq!(listed(&turned1.segments, &turned2.segments, &|p| p), expected, "{} / {}", line1, line2);
+        }
+    }
+
+    #[test]
+    fn every_point_of_an_overlap_with_its_cost() {
+        // (2,0) to (6,0) is shared; wire 1 reaches x after x steps, wire 2 walking the other way after 7 - x
+        let (path1, path2) = (wire("R6"), wire_at("L5", Point::new(7, 0)));
+        let every: Vec<(Point, Steps)> = all_intersections_with(&path1, &path2, OverlapPoints::Every).iter().map(|i| (i.point, i.cost)).collect();
+        let expected: Vec<(Point, Steps)> = (2..=6).map(|x| (Point::new(x, 0), Steps(x + (7 - x)))).collect();
+        assert_eq!(every, expected);
+        // the default stays the two ends
+        let ends: Vec<Point> = all_intersections(&path1, &path2).iter().map(|i| i.point).collect();
+        assert_eq!(ends, vec!(Point::new(2, 0), Point::new(6, 0)));
+    }
+
+    #[test]
+    fn by_distance_yields_nearest_first() {
+        for (line1, line2, distance1, _) in EXAMPLES.iter() {
+            let (path1, path2) = (wire(line1), wire(line2));
+            let first = intersections_by_metric(&path1, &path2, Point::ORIGIN, Metric::Manhattan).next().unwrap();
+            assert_eq!(distance(first.point, Point::ORIGIN), *distance1);
+        }
+        let mut pairs: Vec<(Vec<Segment>, Vec<Segment>)> = EXAMPLES.iter().map(|(line1, line2, _, _)| (wire(line1), wire(line2))).collect();
+        pairs.extend((0..5).map(|seed| random_pair(seed, 30)));
+        for (path1, path2) in pairs {
+            let expected = distinct_except(&all_intersections(&path1, &path2), shared_start(&path1, &path2));
+            for metric in [Metric::Manhattan, Metric::Chebyshev, Metric::SquaredEuclidean] {
+                let yielded: Vec<PointWithCost> = intersections_by_metric(&path1, &path2, Point::ORIGIN, metric).collect();
+                let measures: Vec<i64> = yielded.iter().map(|i| metric.measure(i.point, Point::ORIGIN)).collect();
+                assert!(measures.windows(2).all(|w| w[0] <= w[1]), "{:?}", metric);
+                // each point once, with its cheapest cost
+                assert_eq!(sorted_points(&yielded), sorted_points(&expected), "{:?}", metric);
+            }
+        }
+    }
+
+    #[test]
+    fn intersection_table_within_a_steps_range() {
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let (moves1, moves2) = (moves(line1), moves(line2));
+        let (path1, path2) = (wire(line1), wire(line2));
+        let all = intersections(&path1, &path2, [&moves1, &moves2], Point::ORIGIN, OverlapPoints::Ends);
+        let table = |range| intersection_table(&all, shared_start(&path1, &path2), Point::ORIGIN, Metric::Manhattan, range);
+        // the winners are starred over every crossing, so part 2's 610 is filtered out with its star
+        assert_eq!(table((620, 800)), concat!(
+            "point     distance   steps   moves  kind\n",
+            "(155,4)        159*    726   7/7    cross\n",
+            "(146,46)       192     624   6/6    cross\n",
+        ));
+        assert_eq!(table((i32::MIN, i32::MAX)).lines().count(), 5);
+        assert_eq!(table((624, 624)).lines().count(), 2);
+        assert_eq!(table((0, 609)).lines().count(), 1);
+    }
+
+    #[test]
+    fn best_per_metric_matches_both_parts() {
+        for (line1, line2, distance, steps) in EXAMPLES.iter() {
+            let (moves1, moves2) = (moves(line1), moves(line2));
+            let (path1, path2) = (wire(line1), wire(line2));
+            let all = intersections(&path1, &path2, [&moves1, &moves2], Point::ORIGIN, OverlapPoints::Ends);
+            let best = best_per_metric(&all, Point::ORIGIN, shared_start(&path1, &path2));
+            assert_eq!(best.manhattan.map(|i| i.distance), Some(Distance(*distance)), "{}", line1);
+            assert_eq!(best.steps.map(|i| i.cost()), Some(Steps(*steps)), "{}", line1);
+            let nearest = all.iter().filter(|i| i.point != Point::ORIGIN).map(|i| squared_distance(i.point, Point::ORIGIN)).min();
+            assert_eq!(best.euclidean.map(|i| squared_distance(i.point, Point::ORIGIN)), nearest, "{}", line1);
+        }
+        // no crossings, so nothing
+        let best = best_per_metric(&[], Point::ORIGIN, Some(Point::ORIGIN));
+        assert!(best.manhattan.is_none() && best.steps.is_none() && best.euclidean.is_none());
+    }
+
+    #[test]
+    fn indices_of_the_example_2_winners() {
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let (moves1, moves2) = (moves(line1), moves(line2));
+        let (path1, path2) = (wire(line1), wire(line2));
+        let all = intersections(&path1, &path2, [&moves1, &moves2], Point::ORIGIN, OverlapPoints::Ends);
+        let at = |p: Point| *all.iter().find(|i| i.point == p).unwrap();
+        // part 1: wire 1's R71 meets wire 2's D58
+        let part1 = at(Point::new(155, 4));
+        assert_eq!((part1.move_index, part1.kind, part1.distance), ([6, 6], CrossingKind::Cross, Distance(159)));
+        assert_eq!((moves1[6], moves2[6]), (moves("R71")[0], moves("D58")[0]));
+        // part 2: wire 1's U83 meets wire 2's R83
+        let part2 = at(Point::new(158, -12));
+        assert_eq!((part2.move_index, part2.kind, part2.cost()), ([3, 7], CrossingKind::Cross, Steps(610)));
+        assert_eq!((moves1[3], moves2[7]), (moves("U83")[0], moves("R83")[0]));
+        for i in [part1, part2] {
+            assert!(contains(&path1[i.segment_index[0]], i.point) && contains(&path2[i.segment_index[1]], i.point));
+            assert_eq!(i.steps, [Steps(steps_along(&path1, i.point).unwrap()), Steps(steps_along(&path2, i.point).unwrap())]);
+        }
+        // a merged segment still reports the move the wire was walking
+        let (chunked1, chunked2) = (moves("R2,R3,U4"), moves("U2,R6"));
+        let found = intersections(&wire("R2,R3,U4"), &wire("U2,R6"), [&chunked1, &chunked2], Point::ORIGIN, OverlapPoints::Ends);
+        let crossing = found.iter().find(|i| i.point == Point::new(5, 2)).unwrap();
+        assert_eq!((crossing.segment_index, crossing.move_index), ([1, 1], [2, 1]));
+    }
+
+    #[test]
+    fn explain_snapshot_of_example_2() {
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let (moves1, moves2) = (moves(line1), moves(line2));
+        let (path1, path2) = (wire(line1), wire(line2));
+        let tokens: Vec<Vec<String>> = [line1, line2].iter().map(|l| l.split(',').map(String::from).collect()).collect();
+        let wires = Explanation { segments: [&path1, &path2], moves: [&moves1, &moves2], tokens: [&tokens[0], &tokens[1]],
+                                  syntax: Syntax::Aoc, reference: Point::ORIGIN, metric: Metric::Manhattan };
+        let at = |p| intersection_at(&path1, &path2, [&moves1, &moves2], Point::ORIGIN, p).unwrap();
+        let expected = concat!(
+            "Part 1: (155,4) is where wire 1 move 7, R71, meets wire 2 move 7, D58\n",
+            "  distance from (0,0): |155| + |4| = 159\n",
+            "Part 2: (158,-12) is where wire 1 move 4, U83, meets wire 2 move 8, R83\n",
+            "  wire 1: 188 before move 4 + 18 along it = 206\n",
+            "  wire 2: 401 before move 8 + 3 along it = 404\n",
+            "  steps: 206 + 404 = 610\n",
+        );
+        assert_eq!(explain(&wires, &at(Point::new(155, 4)), &at(Point::new(158, -12))), expected);
+    }
+
+    #[test]
+    fn waypoints_of_the_first_example_wire() {
+        let points: Vec<(Point, Steps)> = waypoints(&wire("R8,U5,L5,D3")).iter().map(|w| (w.point, w.cost)).collect();
+        assert_eq!(points, vec!(
+            (Point::ORIGIN, Steps(0)),
+            (Point::new(8, 0), Steps(8)),
+            (Point::new(8, 5), Steps(13)),
+            (Point::new(3, 5), Steps(18)),
+            (Point::new(3, 2), Steps(21)),
+        ));
+        assert!(waypoints(&[]).is_empty());
+    }
+
+    #[test]
+    fn turns_and_reversals() {
+        assert_eq!(turn_count(&wire("R8,U5,L5,D3")), 3);
+        assert_eq!(turn_count(&wire("R75,D30,R83,U83,L12,D49,R71,U7,L72")), 8);
+        // moves on in one direction are no turn
+        assert_eq!(turn_count(&wire("R2,R3,U1")), 1);
+        assert_eq!(turn_count(&wire("R5")), 0);
+        assert!(!reverses(&wire("R8,U5,L5,D3")));
+        // straight back is no change of orientation, but it does reverse
+        assert_eq!(turn_count(&wire("R5,L5")), 0);
+        assert!(reverses(&wire("R5,L5")));
+        assert!(reverses(&wire("U2,R3,L1,U1")));
+    }
+
+    #[test]
+    fn fingerprints_of_equivalent_paths() {
+        let base = fingerprint(&wire("R7,U2"));
+        assert_eq!(fingerprint(&wire("R3,R4,U1,U1")), base);
+        // walked the other way from the far end
+        assert_eq!(fingerprint(&wire_at("D2,L7", Point::new(7, 2))), base);
+        // one step more, or from another start
+        assert_ne!(fingerprint(&wire("R7,U3")), base);
+        assert_ne!(fingerprint(&wire_at("R7,U2", Point::new(0, 1))), base);
+        // a loop walked the other way round
+        assert_eq!(fingerprint(&wire("R2,U2,L2,D2")), fingerprint(&wire("U2,R2,D2,L2")));
+    }
+
+    #[test]
+    fn two_copies_of_a_wire() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        assert_eq!(fingerprint(&wire(line1)), fingerprint(&wire(line1)));
+        assert_ne!(fingerprint(&wire(line1)), fingerprint(&wire(line2)));
+        // still solved, at the first step away from the start
+        assert_eq!(answers(line1, line1), Some((1, 2)));
+        let copies = solve(wire(line1), wire(line1), Point::ORIGIN).unwrap();
+        assert_eq!((copies.distance_at, copies.steps_at), (Point::new(1, 0), Point::new(1, 0)));
+    }
+
+    #[test]
+    fn coalesce_merges_moves_in_one_direction() {
+        let merged = coalesce(&path_to_segments(moves("R3,R4,U2"), Point::ORIGIN));
+        assert_eq!(merged.len(), 2);
+        assert_eq!((merged[0].end1, merged[0].end2, merged[0].length), (Point::ORIGIN, Point::new(7, 0), 7));
+        assert_eq!((merged[1].steps, merged[1].start), (7, Point::new(7, 0)));
+        // going back is a new segment
+        assert_eq!(coalesce(&path_to_segments(moves("R3,L2"), Point::ORIGIN)).len(), 2);
+        // normalized after merging, walked from where it started
+        let left = wire("L3,L4");
+        assert_eq!(left.len(), 1);
+        assert_eq!((left[0].end1, left[0].end2, left[0].start, left[0].mirrored), (Point::new(-7, 0), Point::ORIGIN, Point::ORIGIN, true));
+        assert_eq!(steps_to(&left, Point::new(-5, 0)), Some(5));
+    }
+
+    #[test]
+    fn segment_limit_names_the_wire_over_it() {
+        // after merging, 4 and 3 segments
+        let (path1, path2) = (wire("R8,U5,L5,D3"), wire("U7,U1,R6,D4"));
+        assert!(check_segment_limit("in.txt", [&path1, &path2], None).is_ok());
+        assert!(check_segment_limit("in.txt", [&path1, &path2], Some(4)).is_ok());
+        let e = check_segment_limit("in.txt", [&path1, &path2], Some(3)).unwrap_err();
+        assert_eq!(e.to_string(), "in.txt: wire 1 has 4 segments, more than the limit of 3");
+        let e = check_segment_limit("in.txt", [&path2, &path1], Some(3)).unwrap_err();
+        assert_eq!(e.to_string(), "in.txt: wire 2 has 4 segments, more than the limit of 3");
+    }
+
+    #[test]
+    fn closest_approach_of_near_misses() {
+        // parallel, 2 apart wherever x is from 2 to 6, so the lowest pair
+        let (upper, lower) = (wire_at("R6", Point::new(0, 5)), wire_at("R6", Point::new(2, 3)));
+        assert_eq!(closest_approach(&upper, &lower), Some((Point::new(2, 5), Point::new(2, 3), 2)));
+        assert_eq!(closest_approach(&lower, &upper), Some((Point::new(2, 3), Point::new(2, 5), 2)));
+        // perpendicular, the end of one beside the other, then past its end as well
+        let across = wire("R4");
+        assert_eq!(closest_approach(&across, &wire_at("U5", Point::new(6, -2))), Some((Point::new(4, 0), Point::new(6, 0), 2)));
+        assert_eq!(closest_approach(&across, &wire_at("U3", Point::new(7, 2))), Some((Point::new(4, 0), Point::new(7, 2), 5)));
+        // crossing wires come 0 apart, away from the shared start
+        let (line1, line2, _, _) = EXAMPLES[0];
+        assert_eq!(closest_approach(&wire(line1), &wire(line2)), Some((Point::new(3, 3), Point::new(3, 3), 0)));
+    }
+
+    // a file in the temp directory holding contents, named apart from other tests' files
+    fn input_file(name: &str, contents: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("day3-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn pair_delimiter_splits_the_first_line() {
+        // the lines after the first non-empty one are never read
+        let path = input_file("pair.txt", b"\nR8,U5,L5,D3;U7,R6,D4,L4\nR1;U1\n");
+        let (wire1, wire2) = read_wires_with(&path, ParseOptions::new(Syntax::Aoc), Some(";")).unwrap();
+        assert_eq!((wire1, wire2), (moves("R8,U5,L5,D3"), moves("U7,R6,D4,L4")));
+        // offsets count from the start of the line, past the delimiter
+        let bad = input_file("pair-bad.txt", b"R8,U5;U7,X6\n");
+        assert_eq!(read_wires_with(&bad, ParseOptions::new(Syntax::Aoc), Some(";")).unwrap_err().to_string(),
+                   format!("{}:1: parse error at offset 9: invalid direction 'X'", bad));
+        let one = input_file("pair-one.txt", b"R8,U5\n");
+        assert_eq!(read_wires_with(&one, ParseOptions::new(Syntax::Aoc), Some(";")).unwrap_err().to_string(), format!("{}: expected 2 wires, found 1", one));
+        for file in [&path, &bad, &one] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn binary_inputs_by_magic_or_option() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let mut bytes = vec!();
+        compact::write(&mut bytes, [&moves(line1), &moves(line2)]).unwrap();
+        let binary = input_file("compact.bin", &bytes);
+        let text = input_file("compact.txt", format!("{}\n{}\n", line1, line2).as_bytes());
+        let forced = ParseOptions { binary: true, ..ParseOptions::new(Syntax::Aoc) };
+        assert_eq!(read_wires_with(&binary, ParseOptions::new(Syntax::Aoc), None).unwrap(), (moves(line1), moves(line2)));
+        assert_eq!(read_wires_with(&binary, forced, None).unwrap(), (moves(line1), moves(line2)));
+        assert_eq!(read_wires_with(&text, ParseOptions::new(Syntax::Aoc), None).unwrap(), (moves(line1), moves(line2)));
+        assert_eq!(read_wires_with(&text, forced, None).unwrap_err().to_string(),
+                   format!("{}: parse error at offset 0: not a compact wire file", text));
+        for file in [&binary, &text] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn solve_file_per_batch_row() {
+        let solved = |path: &str| solve_file(path, ParseOptions::new(Syntax::Aoc), Point::ORIGIN, Point::ORIGIN, None, OverlapCount::PerRun)
+            .map(|(solution, count)| (solution.map(|s| (s.distance.0, s.steps.0)), count));
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let good = input_file("batch-good.txt", format!("{}\n{}\n", line1, line2).as_bytes());
+        assert_eq!(solved(&good).unwrap(), (Some((159, 610)), 4));
+        let apart = input_file("batch-apart.txt", b"R5,U5\nL5,D5\n");
+        assert_eq!(solved(&apart).unwrap(), (None, 0));
+        // a bad row fails on its own
+        let bad = input_file("batch-bad.txt", b"R5,U5\n");
+        assert_eq!(solved(&bad).unwrap_err().to_string(), format!("{}: expected 2 wires, found 1", bad));
+        assert!(solved(&format!("{}.missing", bad)).is_err());
+        for file in [&good, &apart, &bad] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn obstacles_refuse_a_wire_along_an_edge_or_through() {
+        let obstacle = [rect(2, 2, 4, 4)];
+        let check = |line1: &str, line2: &str| {
+            let (moves1, moves2) = (moves(line1), moves(line2));
+            check_obstacles("in.txt", [(&moves1, Point::ORIGIN), (&moves2, Point::ORIGIN)], &obstacle, None).map_err(|e| e.to_string())
+        };
+        // passing just below and just left of it
+        assert_eq!(check("U1,R10", "R1,U10"), Ok(()));
+        // edges belong to the obstacle, so skimming one is refused where it starts
+        assert_eq!(check("U2,R10", "R1,U10"), Err("in.txt: wire 1 move 2 enters the obstacle (2,2) to (4,4) at (2,2)".to_string()));
+        // straight through, entering from below
+        assert_eq!(check("U1,R10", "R3,U10"), Err("in.txt: wire 2 move 2 enters the obstacle (2,2) to (4,4) at (3,2)".to_string()));
+        // and from above, walking down
+        assert_eq!(check("U1,R10", "U8,R3,D10"), Err("in.txt: wire 2 move 3 enters the obstacle (2,2) to (4,4) at (3,4)".to_string()));
+        assert!(check_obstacles("in.txt", [(&moves("U2,R10"), Point::ORIGIN), (&moves("R3,U10"), Point::ORIGIN)], &[], None).is_ok());
+        // crossings inside one are never answers
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let (d, by_distance, _) = closest_intersect(wire(line1), wire(line2), Point::ORIGIN, Metric::Manhattan, 0, &[rect(3, 3, 3, 3)], None).unwrap();
+        assert_eq!((d, by_distance.point), (11, Point::new(6, 5)));
+    }
+
+    fn parse_error(line: &str, syntax: Syntax) -> (usize, String) {
+        let e = parse_path(line, ParseOptions::new(syntax)).unwrap_err();
+        (e.offset, e.message)
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_move() {
+        assert_eq!(parse_error("R8,U5,X5,D3", Syntax::Aoc), (6, "invalid direction 'X'".to_string()));
+        assert_eq!(parse_error("R8,U5x,L5", Syntax::Aoc), (3, "invalid distance '5x'".to_string()));
+        // spaces around a move are skipped, so the offset is where it starts
+        assert_eq!(parse_error("R8, U,L5", Syntax::Aoc), (4, "invalid distance ''".to_string()));
+        assert_eq!(parse_error("R8,,L5", Syntax::Aoc), (3, "empty move".to_string()));
+        assert_eq!(parse_error("R8,U5\u{e9}", Syntax::Aoc).0, 3);
+        assert_eq!(ParseError { offset: 6, message: "empty move".to_string() }.to_string(), "parse error at offset 6: empty move");
+    }
+
+    #[test]
+    fn signed_distances_flip_the_direction() {
+        assert_eq!(parse_error("R8,R-5", Syntax::Aoc), (3, "negative distance '-5'".to_string()));
+        let signed = ParseOptions { signed_distance: true, ..ParseOptions::new(Syntax::Aoc) };
+        let (r_minus, flipped) = (parse_path("R-5", signed), parse_path("R8,R-5,U-3", signed));
+        let too_big = parse_path("R-2147483648", signed).map_err(|e| e.message);
+        let ends = |path: Vec<PathSegment>| -> Vec<(Point, Point, Point)> {
+            wire_segments(path, &NO_HEADER, Point::ORIGIN).iter().map(|s| (s.end1, s.end2, s.start)).collect()
+        };
+        assert_eq!(ends(r_minus.unwrap()), ends(moves("L5")));
+        assert_eq!(flipped.unwrap(), moves("R8,L5,D3"));
+        assert_eq!(too_big, Err("invalid distance '-2147483648'".to_string()));
+    }
+
+    #[test]
+    fn xy_syntax_round_trips() {
+        let line = "x+8,y+5,x-5,y-3";
+        let path = parse_path(line, ParseOptions::new(Syntax::Xy)).unwrap();
+        assert_eq!(path, moves("R8,U5,L5,D3"));
+        let written: Vec<String> = path.iter().map(|m| Syntax::Xy.token(m)).collect();
+        assert_eq!(written.join(","), line);
+        for (line1, _, _, _) in EXAMPLES.iter() {
+            let xy: Vec<String> = moves(line1).iter().map(|m| Syntax::Xy.token(m)).collect();
+            assert_eq!(parse_path(&xy.join(","), ParseOptions::new(Syntax::Xy)).unwrap(), moves(line1));
+        }
+        assert_eq!(parse_error("x+8,z+2", Syntax::Xy), (4, "invalid direction 'z+'".to_string()));
+        assert_eq!(parse_error("x+8,R2", Syntax::Xy), (4, "invalid direction 'R2'".to_string()));
+        assert_eq!(parse_error("x+8,y", Syntax::Xy), (4, "invalid direction 'y'".to_string()));
+    }
+
+    #[test]
+    fn wires_from_different_starts() {
+        assert_eq!(split_start("(100,100): R10,U50").unwrap(), (Some(Point::new(100, 100)), " R10,U50", 10));
+        assert_eq!(split_start("R10,U50").unwrap(), (None, "R10,U50", 0));
+        let error = |line: &str| split_start(line).unwrap_err().message;
+        assert_eq!(error("(1,2 R5"), "unclosed start");
+        assert_eq!(error("(1,2) R5"), "expected ':' after the start");
+        assert_eq!(error("(1,x): R5"), "invalid start (1,x)");
+        // each from its own line, crossing at (110,120), 230 from (0,0) and far from either start
+        let path = input_file("starts.txt", b"(100,100): R10,U50\n(130,120): x1: L30\n");
+        let ((moves1, moves2), headers) = read_wire_headers(&path, ParseOptions::new(Syntax::Aoc), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let (path1, path2) = (wire_segments(moves1, &headers[0], Point::ORIGIN), wire_segments(moves2, &headers[1], Point::ORIGIN));
+        assert_eq!(shared_start(&path1, &path2), None);
+        let solution = solve(path1.clone(), path2.clone(), Point::ORIGIN).unwrap();
+        assert_eq!((solution.distance_at, solution.distance.0, solution.steps.0), (Point::new(110, 120), 230, 50));
+        // a wire passing through the other's start meets it there, as nothing is shared
+        let through = solve(wire("R10"), wire_at("D10", Point::new(5, 5)), Point::ORIGIN).unwrap();
+        assert_eq!((through.distance_at, through.steps.0), (Point::new(5, 0), 10));
+        // or at its start, 0 away; walked towards each other every shared point costs 5
+        let at_start = solve(wire("R10"), wire_at("L10", Point::new(5, 0)), Point::ORIGIN).unwrap();
+        assert_eq!((at_start.distance_at, at_start.distance.0, at_start.steps.0), (Point::ORIGIN, 0, 5));
+    }
+
+    #[test]
+    fn moving_the_start_moves_every_crossing() {
+        let (dx, dy) = (-37, 12);
+        let shift = |p: Point| Point::new(p.x() + dx, p.y() + dy);
+        for (line1, line2, distance1, steps) in EXAMPLES.iter() {
+            let listed = |path1: &[Segment], path2: &[Segment]| -> Vec<(Point, Steps)> {
+                all_intersections(path1, path2).iter().map(|i| (i.point, i.cost)).collect()
+            };
+            let here = listed(&wire(line1), &wire(line2));
+            let start = shift(Point::ORIGIN);
+            let there = listed(&wire_at(line1, start), &wire_at(line2, start));
+            assert_eq!(there, here.iter().map(|(p, cost)| (shift(*p), *cost)).collect::<Vec<(Point, Steps)>>());
+            // measured from the moved start the answers stay
+            let solution = solve(wire_at(line1, start), wire_at(line2, start), start).unwrap();
+            assert_eq!((solution.distance.0, solution.steps.0), (*distance1, *steps));
+        }
+    }
+
+    #[test]
+    fn prefix_cuts_after_so_many_steps() {
+        let line = "R8,U5,L5,D3";
+        let (path, whole) = (wire(line), Wire::new(moves(line), Point::ORIGIN));
+        assert!(prefix(&path, 0).is_empty());
+        for steps in 1..=25 {
+            let cut = prefix(&path, steps);
+            let walked: i32 = cut.iter().map(|s| s.length).sum();
+            assert_eq!(walked, min(steps, 21), "{} steps", steps);
+            // it ends where the wire is after that many steps, walked the same way
+            let last = cut.last().unwrap();
+            assert_eq!(Some(last.end()), whole.point_at_step(min(steps, 21)), "{} steps", steps);
+            assert_eq!(last.start, path[cut.len() - 1].start);
+            assert_eq!(last.steps, path[cut.len() - 1].steps);
+            assert!(last.end1.x() <= last.end2.x() && last.end1.y() <= last.end2.y());
+        }
+        // cut at a corner, the next segment is left out entirely
+        assert_eq!(prefix(&path, 13).len(), 2);
+        // a crossing is reached by both prefixes only once both wires have walked to it
+        let other = wire("U7,R6,D4,L4");
+        let part2 = |steps| solve(prefix(&path, steps), prefix(&other, steps), Point::ORIGIN).map(|s| s.steps.0);
+        assert_eq!(part2(14), None);
+        assert_eq!(part2(15), Some(30));
+    }
+
+    #[test]
+    fn steps_in_all_four_directions() {
+        // a turn after 2 steps, then 5 steps the given way; 3 steps along is 5 from the start
+        let cases = [
+            ("U2,R5", Point::new(3, 2), Point::new(5, 2)),
+            ("U2,L5", Point::new(-3, 2), Point::new(-5, 2)),
+            ("R2,U5", Point::new(2, 3), Point::new(2, 5)),
+            ("R2,D5", Point::new(2, -3), Point::new(2, -5)),
+        ];
+        for (line, along, end) in cases.iter() {
+            let s = wire(line)[1];
+            assert_eq!((s.start, s.end(), s.steps, s.length), (wire(line)[0].end(), *end, 2, 5), "{}", line);
+            assert_eq!(cost_for_segment(*along, &s), 5, "{}", line);
+            assert_eq!(cost_for_segment(*end, &s), 7, "{}", line);
+            // a wire of a single point there adds no steps of its own
+            let dot = Segment::try_from(&*format!("{},{},{},{}", along.x(), along.y(), along.x(), along.y())).unwrap();
+            assert_eq!(all_intersections(&wire(line), &[dot])[0].cost, Steps(5), "{}", line);
+        }
+    }
+
+    #[test]
+    fn segment_from_coordinates() {
+        let s = Segment::try_from("0,0,8,0").unwrap();
+        assert_eq!((s.end1, s.end2, s.length, s.steps), (Point::ORIGIN, Point::new(8, 0), 8, 0));
+        let back = Segment::try_from(" 3, 7, 3, -2").unwrap();
+        assert_eq!((back.end1, back.end2, back.length, back.mirrored), (Point::new(3, -2), Point::new(3, 7), 9, true));
+        // it meets the wire it was cut from the same way
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let crossing = all_intersections(&[Segment::try_from("6,7,6,3").unwrap()], &wire(line1));
+        assert_eq!(crossing.iter().map(|i| i.point).collect::<Vec<Point>>(), vec!(Point::new(6, 5)));
+        assert!(wire(line2).iter().any(|w| (w.end1, w.end2) == (Point::new(6, 3), Point::new(6, 7))));
+        let error = |s: &str| Segment::try_from(s).map(|_| ()).unwrap_err();
+        assert_eq!((error("0,0,x,0").offset, error("0,0,x,0").message), (4, "invalid coordinate 'x'".to_string()));
+        assert_eq!(error("0,0,8").message, "expected 4 coordinates, found 3");
+        assert_eq!(error("0,0,8,1").message, "segment (0,0)-(8,1) is not axis aligned");
+    }
+
+    fn summary(index: usize, color: bool) -> String {
+        let (line1, line2, _, _) = EXAMPLES[index];
+        let (moves1, moves2) = (moves(line1), moves(line2));
+        let (path1, path2) = (wire(line1), wire(line2));
+        let solution = solve(path1.clone(), path2.clone(), Point::ORIGIN).unwrap();
+        solve_summary([&moves1, &moves2], [&path1, &path2], (solution.distance.0 as i64, solution.distance_at),
+                      (solution.steps.0, solution.steps_at), color)
+    }
+
+    #[test]
+    fn summary_snapshots_of_the_examples() {
+        let expected = [
+            concat!(
+                "Wire 1: 4 moves, 4 segments, length 21\n",
+                "Wire 2: 4 moves, 4 segments, length 21\n",
+                "Part 1:  6 at (3,3)\n",
+                "Part 2: 30 at (6,5)\n",
+            ),
+            concat!(
+                "Wire 1: 9 moves, 9 segments, length 482\n",
+                "Wire 2: 8 moves, 8 segments, length 484\n",
+                "Part 1: 159 at (155,4)\n",
+                "Part 2: 610 at (158,-12)\n",
+            ),
+            concat!(
+                "Wire 1: 11 moves, 11 segments, length 573\n",
+                "Wire 2: 10 moves, 10 segments, length 367\n",
+                "Part 1: 135 at (124,11)\n",
+                "Part 2: 410 at (107,47)\n",
+            ),
+        ];
+        for (index, expected) in expected.iter().enumerate() {
+            assert_eq!(summary(index, false), *expected);
+        }
+        assert!(summary(0, true).starts_with("\x1b[1mWire 1:\x1b[0m 4 moves"));
+    }
+
+    #[test]
+    fn farthest_intersection_of_the_examples() {
+        let farthest = |(line1, line2, _, _): (&str, &str, i32, i32), reference| {
+            farthest_intersection(&wire(line1), &wire(line2), reference).map(|i| (i.point, i.cost))
+        };
+        assert_eq!(farthest(EXAMPLES[0], Point::ORIGIN), Some((Point::new(6, 5), Steps(30))));
+        assert_eq!(farthest(EXAMPLES[1], Point::ORIGIN), Some((Point::new(146, 46), Steps(624))));
+        // measured from the other crossing
+        assert_eq!(farthest(EXAMPLES[0], Point::new(6, 5)), Some((Point::new(3, 3), Steps(40))));
+        // the shared start is never one
+        assert!(farthest_intersection(&wire("R5,U5"), &wire("L5,D5"), Point::ORIGIN).is_none());
+    }
+
+    #[test]
+    fn each_metric_picks_its_own_crossing() {
+        // a short vertical across each of y=0, y=2 and y=4, at (6,0), (5,2) and (4,4)
+        let (path1, path2) = (wire_at("U2,L1,U2,L1,U2", Point::new(6, -1)), wire("R10,U2,L10,U2,R10"));
+        let best = |metric| {
+            let (score, by_metric, _) = closest_intersect(path1.clone(), path2.clone(), Point::ORIGIN, metric, 0, &[], None).unwrap();
+            (by_metric.point, score)
+        };
+        // 6 + 0 beats 5 + 2 and 4 + 4
+        assert_eq!(best(Metric::Manhattan), (Point::new(6, 0), 6));
+        // max(4, 4) beats max(5, 2) and max(6, 0)
+        assert_eq!(best(Metric::Chebyshev), (Point::new(4, 4), 4));
+        // 25 + 4 beats 16 + 16 and 36 + 0
+        assert_eq!(best(Metric::SquaredEuclidean), (Point::new(5, 2), 29));
+        assert_eq!(Metric::parse("euclidean"), Some(Metric::SquaredEuclidean));
+        assert_eq!(Metric::parse("taxicab"), None);
+    }
+
+    #[test]
+    fn weighted_extremes_are_the_two_parts() {
+        for (line1, line2, distance1, steps) in EXAMPLES.iter() {
+            let (path1, path2) = (wire(line1), wire(line2));
+            let solution = solve(path1.clone(), path2.clone(), Point::ORIGIN).unwrap();
+            let by_distance = best_weighted(&path1, &path2, Point::ORIGIN, 1.0, 0.0).unwrap();
+            assert_eq!((distance(by_distance.point, Point::ORIGIN), by_distance.point), (*distance1, solution.distance_at));
+            let by_steps = best_weighted(&path1, &path2, Point::ORIGIN, 0.0, 1.0).unwrap();
+            assert_eq!((by_steps.cost.0, by_steps.point), (*steps, solution.steps_at));
+        }
+        // (3,3) scores 6 + 40 and (6,5) 11 + 30
+        let (line1, line2, _, _) = EXAMPLES[0];
+        assert_eq!(best_weighted(&wire(line1), &wire(line2), Point::ORIGIN, 1.0, 1.0).unwrap().point, Point::new(6, 5));
+        assert_eq!(best_weighted(&wire(line1), &wire(line2), Point::ORIGIN, 3.0, 1.0).unwrap().point, Point::new(3, 3));
+    }
+
+    #[test]
+    fn zero_length_moves_add_nothing() {
+        let segments = |line: &str| normalize(path_to_segments(moves(line), Point::ORIGIN));
+        assert_eq!(segments("R5,U0,R3").len(), 2);
+        assert!(segments("U0,L0").is_empty());
+        // an empty segment at (5,0) would also match as vertical against the other wire
+        let other = wire_at("U4", Point::new(5, -2));
+        let listed = |path: &[Segment]| -> Vec<(Point, Steps, String)> {
+            all_intersections(path, &other).iter().map(|i| (i.point, i.cost, i.kind.to_string())).collect()
+        };
+        assert_eq!(listed(&segments("R5,U0,R3")), listed(&segments("R5,R3")));
+        assert!(listed(&segments("U0")).is_empty());
+        let (line1, line2, distance1, steps) = EXAMPLES[0];
+        let padded = line1.replace(',', ",D0,");
+        assert_eq!(answers(&padded, line2), Some((distance1, steps)));
+    }
+
+    fn rect(x1: i32, y1: i32, x2: i32, y2: i32) -> Rect {
+        Rect { min: Point::new(x1, y1), max: Point::new(x2, y2) }
+    }
+
+    #[test]
+    fn clip_segment_cases() {
+        let window = rect(0, 0, 10, 10);
+        let ends = |s: Option<Segment>| s.map(|s| (s.end1, s.end2, s.start, s.steps, s.length));
+        let p = Point::new;
+        // (from, to) of a single move walked after 100 steps, and what is left inside the window
+        let cases = [
+            ((2, 3), (8, 3), Some(((2, 3), (8, 3), (2, 3), 100, 6))),
+            ((-5, 3), (5, 3), Some(((0, 3), (5, 3), (0, 3), 105, 5))),
+            ((5, 3), (-5, 3), Some(((0, 3), (5, 3), (5, 3), 100, 5))),
+            ((-5, 3), (15, 3), Some(((0, 3), (10, 3), (0, 3), 105, 10))),
+            ((4, 15), (4, -5), Some(((4, 0), (4, 10), (4, 10), 105, 10))),
+            // along an edge, touching a corner, and outside on each side
+            ((-3, 0), (3, 0), Some(((0, 0), (3, 0), (0, 0), 103, 3))),
+            ((10, 10), (10, 14), Some(((10, 10), (10, 10), (10, 10), 100, 0))),
+            ((-5, 3), (-1, 3), None),
+            ((11, 3), (15, 3), None),
+            ((3, -5), (3, -1), None),
+            ((3, 11), (3, 15), None),
+        ];
+        for (from, to, expected) in cases.iter() {
+            let (from, to) = (p(from.0, from.1), p(to.0, to.1));
+            let s = normalize(vec!(Segment { end1: from, end2: to, steps: 100, start: from, length: distance(from, to), mirrored: false, weight: 1 }))[0];
+            let expected = expected.map(|(e1, e2, start, steps, length)| (p(e1.0, e1.1), p(e2.0, e2.1), p(start.0, start.1), steps, length));
+            assert_eq!(ends(clip_segment(&s, window)), expected, "{} to {}", from, to);
+        }
+        assert!(window.contains(p(0, 10)) && !window.contains(p(-1, 5)));
+        assert_eq!(window.union(&rect(-4, 2, 3, 12)), rect(-4, 0, 10, 12));
+    }
+
+    #[test]
+    fn solving_inside_a_window() {
+        let (path1, path2) = (wire("R10,U4,L10,U4,R10"), wire("U10,R5,D10"));
+        let window = rect(2, -1, 8, 10);
+        // the first wire leaves and comes back twice, so three pieces
+        let (clipped1, clipped2) = (clip_to_rect(&path1, window), clip_to_rect(&path2, window));
+        let pieces: Vec<(Point, i32)> = clipped1.iter().map(|s| (s.start, s.steps)).collect();
+        assert_eq!(pieces, vec!((Point::new(2, 0), 2), (Point::new(8, 4), 16), (Point::new(2, 8), 30)));
+        // outside, the wires run along each other from (0,4) to (0,8), with the best answers of both parts
+        let whole = solve(path1, path2, Point::ORIGIN).unwrap();
+        assert_eq!((whole.distance_at, whole.steps_at, whole.steps.0), (Point::new(0, 4), Point::new(0, 4), 28));
+        // the start is outside too, so distances come from the reference
+        let inside = |reference| {
+            let solution = solve(clipped1.clone(), clipped2.clone(), reference).unwrap();
+            (solution.distance_at, solution.distance.0, solution.steps_at, solution.steps.0)
+        };
+        assert_eq!(inside(Point::ORIGIN), (Point::new(5, 0), 5, Point::new(5, 0), 30));
+        assert_eq!(inside(Point::new(5, 5)), (Point::new(5, 4), 1, Point::new(5, 0), 30));
+    }
+
+    #[test]
+    fn centroid_of_rectangles() {
+        assert_eq!(centroid(&wire("R4,U2,L4,D2")), Point::new(2, 1));
+        assert_eq!(centroid(&wire_at("R10,U6,L10,D6", Point::new(100, -50))), Point::new(105, -47));
+        // halves round away from zero
+        assert_eq!(centroid(&wire("R3")), Point::new(2, 0));
+        assert_eq!(centroid(&[]), Point::ORIGIN);
+    }
+
+    #[test]
+    fn clip_to_rect_of_whole_wires() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let path1 = wire(line1);
+        let ends = |segments: &[Segment]| -> Vec<(Point, Point, i32)> { segments.iter().map(|s| (s.end1, s.end2, s.steps)).collect() };
+        assert_eq!(ends(&clip_to_rect(&path1, rect(-1, -1, 10, 10))), ends(&path1));
+        assert!(clip_to_rect(&path1, rect(20, 20, 30, 30)).is_empty());
+        // the first two moves straddle the edges, the last two are outside
+        assert_eq!(ends(&clip_to_rect(&path1, rect(4, -2, 10, 3))),
+                   vec!((Point::new(4, 0), Point::new(8, 0), 4), (Point::new(8, 0), Point::new(8, 3), 8)));
+        // both crossings are inside, still at the steps of the whole wires
+        let window = rect(2, 2, 7, 6);
+        let clipped = all_intersections(&clip_to_rect(&path1, window), &clip_to_rect(&wire(line2), window));
+        let mut found: Vec<(Point, Steps)> = clipped.iter().map(|i| (i.point, i.cost)).collect();
+        found.sort_by_key(|(p, _)| (p.x(), p.y()));
+        assert_eq!(found, vec!((Point::new(3, 3), Steps(40)), (Point::new(6, 5), Steps(30))));
+    }
+}
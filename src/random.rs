@@ -0,0 +1,58 @@
+use crate::PathSegment;
+
+const DIRECTIONS: [char; 4] = ['U', 'D', 'L', 'R'];
+// the same range of move lengths as the puzzle inputs
+const MAX_DISTANCE: u64 = 1000;
+
+// splitmix64, so the same seed gives the same wires on every platform
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform enough in 0..n for the small n used here
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+pub fn random_wire(rng: &mut Rng, count: usize) -> Vec<PathSegment> {
+    (0..count).map(|_| PathSegment {
+        direction: DIRECTIONS[rng.below(4) as usize],
+        distance: 1 + rng.below(MAX_DISTANCE) as i32,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitmix64_reference_outputs() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_wire() {
+        let a = random_wire(&mut Rng::new(42), 5000);
+        assert_eq!(a.len(), 5000);
+        assert_eq!(a, random_wire(&mut Rng::new(42), 5000));
+        assert_ne!(a, random_wire(&mut Rng::new(43), 5000));
+        assert!(a.iter().all(|m| DIRECTIONS.contains(&m.direction) && (1..=MAX_DISTANCE as i32).contains(&m.distance)));
+    }
+}
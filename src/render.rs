@@ -0,0 +1,349 @@
+use std::cmp::max;
+
+use crate::{clip_to_rect, Point, PointWithCost, Rect, Segment};
+
+const BRAILLE_BASE: u32 = 0x2800;
+const INTERSECTION_MARKER: char = '●';
+const HEAT_MARKER: char = '█';
+
+// ANSI 256 color cube, cold (cheap) to hot (expensive)
+const HEAT_COLORS: [u8; 10] = [21, 27, 33, 39, 45, 49, 118, 226, 208, 196];
+// the same gradient as intensity characters for --no-color
+const HEAT_INTENSITY: [char; 10] = ['.', ':', '-', '=', '+', '*', 'o', '#', '%', '@'];
+
+// bit for the dot at (column, row) inside one 2x4 braille cell
+const BRAILLE_DOTS: [[u8; 4]; 2] = [
+    [0x01, 0x02, 0x04, 0x40],
+    [0x08, 0x10, 0x20, 0x80],
+];
+
+fn braille_char(bits: u8) -> char {
+    std::char::from_u32(BRAILLE_BASE + bits as u32).unwrap()
+}
+
+pub fn bounds(wires: &[&[Segment]]) -> Rect {
+    let mut points = wires.iter().flat_map(|w| w.iter()).flat_map(|s| vec![s.end1, s.end2]);
+    let first = points.next().unwrap_or(Point::ORIGIN);
+    let mut min = first;
+    let mut max = first;
+    for p in points {
+        min = Point::new(min.x().min(p.x()), min.y().min(p.y()));
+        max = Point::new(max.x().max(p.x()), max.y().max(p.y()));
+    }
+    Rect { min, max }
+}
+
+// maps world coordinate v in [low, high] onto bucket 0..buckets-1
+fn bucket(v: i32, low: i32, high: i32, buckets: usize) -> usize {
+    if high == low || buckets <= 1 {
+        return 0;
+    }
+    let offset = (v - low) as i64 * (buckets as i64 - 1);
+    ((offset + (high - low) as i64 / 2) / (high - low) as i64) as usize
+}
+
+#[derive(Copy, Clone)]
+struct Marker {
+    symbol: char,
+    color: Option<u8>,
+}
+
+fn paint(marker: Marker) -> String {
+    match marker.color {
+        Some(color) => format!("\x1b[38;5;{}m{}\x1b[0m", color, marker.symbol),
+        None => marker.symbol.to_string(),
+    }
+}
+
+struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    markers: Vec<Option<Marker>>,
+}
+
+impl BrailleCanvas {
+    fn new(width: usize, height: usize) -> BrailleCanvas {
+        BrailleCanvas { width, height, cells: vec![0; width * height], markers: vec![None; width * height] }
+    }
+
+    fn set_dot(&mut self, dx: usize, dy: usize) {
+        let cell = (dy / 4) * self.width + dx / 2;
+        self.cells[cell] |= BRAILLE_DOTS[dx % 2][dy % 4];
+    }
+
+    fn mark(&mut self, dx: usize, dy: usize, marker: Marker) {
+        self.markers[(dy / 4) * self.width + dx / 2] = Some(marker);
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let i = row * self.width + col;
+                match self.markers[i] {
+                    Some(marker) => out.push_str(&paint(marker)),
+                    None => out.push(braille_char(self.cells[i])),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+struct Projection {
+    bounds: Rect,
+    dots_x: usize,
+    dots_y: usize,
+}
+
+impl Projection {
+    // world y grows upwards, dot rows grow downwards
+    fn dot(&self, p: Point) -> (usize, usize) {
+        let dx = bucket(p.x(), self.bounds.min.x(), self.bounds.max.x(), self.dots_x);
+        let dy = self.dots_y - 1 - bucket(p.y(), self.bounds.min.y(), self.bounds.max.y(), self.dots_y);
+        (dx, dy)
+    }
+}
+
+fn draw_segment(canvas: &mut BrailleCanvas, projection: &Projection, s: &Segment) {
+    let (x1, y1) = projection.dot(s.end1);
+    let (x2, y2) = projection.dot(s.end2);
+    for dx in x1.min(x2)..=x1.max(x2) {
+        for dy in y1.min(y2)..=y1.max(y2) {
+            canvas.set_dot(dx, dy);
+        }
+    }
+}
+
+fn default_height(bounds: &Rect, width: usize) -> usize {
+    // a braille dot is roughly square, so keep the world aspect ratio in dot space
+    let world_w = max(1, bounds.max.x() - bounds.min.x()) as usize;
+    let world_h = max(1, bounds.max.y() - bounds.min.y()) as usize;
+    max(1, (width * 2 * world_h / world_w).div_ceil(4))
+}
+
+// COLUMNS is the only source: asking the terminal itself takes an ioctl this crate has no binding
+// for. Shells set COLUMNS without exporting it, so unless the user exports it or passes --width
+// this is 80
+pub fn terminal_width() -> usize {
+    std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).filter(|w| *w > 0).unwrap_or(80)
+}
+
+fn wire_canvas(wire1: &[Segment], wire2: &[Segment], width: usize, height: Option<usize>) -> (BrailleCanvas, Projection) {
+    let bounds = bounds(&[wire1, wire2]);
+    let height = height.unwrap_or_else(|| default_height(&bounds, width));
+    window_canvas(wire1, wire2, bounds, width, height)
+}
+
+// segments must already be clipped to bounds
+fn window_canvas(wire1: &[Segment], wire2: &[Segment], bounds: Rect, width: usize, height: usize) -> (BrailleCanvas, Projection) {
+    let projection = Projection { bounds, dots_x: width * 2, dots_y: height * 4 };
+    let mut canvas = BrailleCanvas::new(width, height);
+    for s in wire1.iter().chain(wire2.iter()) {
+        draw_segment(&mut canvas, &projection, s);
+    }
+    (canvas, projection)
+}
+
+pub fn braille(wire1: &[Segment], wire2: &[Segment], intersections: &[Point], width: usize, height: Option<usize>) -> String {
+    let (mut canvas, projection) = wire_canvas(wire1, wire2, width, height);
+    for p in intersections {
+        let (dx, dy) = projection.dot(*p);
+        canvas.mark(dx, dy, Marker { symbol: INTERSECTION_MARKER, color: None });
+    }
+    canvas.render()
+}
+
+// draws only the part of the wires inside bounds, e.g. the visible area of a viewer
+pub fn braille_window(wire1: &[Segment], wire2: &[Segment], intersections: &[Point], bounds: Rect, width: usize, height: Option<usize>) -> String {
+    let height = height.unwrap_or_else(|| default_height(&bounds, width));
+    let (wire1, wire2) = (clip_to_rect(wire1, bounds), clip_to_rect(wire2, bounds));
+    let (mut canvas, projection) = window_canvas(&wire1, &wire2, bounds, width, height);
+    for p in intersections.iter().filter(|p| bounds.contains(**p)) {
+        let (dx, dy) = projection.dot(*p);
+        canvas.mark(dx, dy, Marker { symbol: INTERSECTION_MARKER, color: None });
+    }
+    canvas.render()
+}
+
+// maps cost in [min, max] onto one of `bins` gradient steps
+fn cost_bin(cost: i32, min: i32, max: i32, bins: usize) -> usize {
+    if max <= min {
+        return 0;
+    }
+    let bin = (cost - min) as i64 * bins as i64 / (max - min + 1) as i64;
+    (bin as usize).min(bins - 1)
+}
+
+fn heat_marker(bin: usize, color: bool) -> Marker {
+    if color {
+        Marker { symbol: HEAT_MARKER, color: Some(HEAT_COLORS[bin]) }
+    } else {
+        Marker { symbol: HEAT_INTENSITY[bin], color: None }
+    }
+}
+
+fn legend(min: i32, max: i32, color: bool) -> String {
+    let gradient: String = (0..HEAT_COLORS.len()).map(|bin| paint(heat_marker(bin, color))).collect();
+    format!("steps {} {} {}\n", min, gradient, max)
+}
+
+pub fn heatmap(wire1: &[Segment], wire2: &[Segment], intersections: &[PointWithCost], width: usize, height: Option<usize>, color: bool) -> String {
+    let (mut canvas, projection) = wire_canvas(wire1, wire2, width, height);
+    let min = intersections.iter().map(|i| i.cost.0).min().unwrap_or(0);
+    let max = intersections.iter().map(|i| i.cost.0).max().unwrap_or(0);
+    // draw the most expensive first so the cheapest crossing wins a shared cell
+    let mut sorted = intersections.to_vec();
+    sorted.sort_by_key(|i| -i.cost.0);
+    for i in sorted {
+        let (dx, dy) = projection.dot(i.point);
+        canvas.mark(dx, dy, heat_marker(cost_bin(i.cost.0, min, max, HEAT_COLORS.len()), color));
+    }
+    canvas.render() + &legend(min, max, color)
+}
+
+// marks every bucket a wire passes through, one bucket row or column per segment
+fn coverage_mask(wire: &[Segment], bounds: &Rect, bins: usize) -> Vec<bool> {
+    let mut mask = vec![false; bins * bins];
+    for s in wire {
+        let (x1, x2) = (bucket(s.end1.x(), bounds.min.x(), bounds.max.x(), bins), bucket(s.end2.x(), bounds.min.x(), bounds.max.x(), bins));
+        let (y1, y2) = (bucket(s.end1.y(), bounds.min.y(), bounds.max.y(), bins), bucket(s.end2.y(), bounds.min.y(), bounds.max.y(), bins));
+        for bx in x1.min(x2)..=x1.max(x2) {
+            for by in y1.min(y2)..=y1.max(y2) {
+                mask[(bins - 1 - by) * bins + bx] = true;
+            }
+        }
+    }
+    mask
+}
+
+// bins x bins pixels, top row first: 0 = empty, 1 = one wire, 2 = both wires
+pub fn raster(wire1: &[Segment], wire2: &[Segment], bounds: &Rect, bins: usize) -> Vec<u8> {
+    let mask1 = coverage_mask(wire1, bounds, bins);
+    let mask2 = coverage_mask(wire2, bounds, bins);
+    mask1.iter().zip(mask2.iter()).map(|(a, b)| *a as u8 + *b as u8).collect()
+}
+
+pub fn pgm(wire1: &[Segment], wire2: &[Segment], bins: usize) -> Vec<u8> {
+    let mut out = format!("P5\n{} {}\n255\n", bins, bins).into_bytes();
+    let bounds = bounds(&[wire1, wire2]);
+    out.extend(raster(wire1, wire2, &bounds, bins).iter().map(|d| [0u8, 128, 255][*d as usize]));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::all_intersections;
+    use crate::tests::{wire, EXAMPLES};
+
+    #[test]
+    fn bucket_spreads_the_range_over_every_bucket() {
+        assert_eq!(bucket(0, 0, 100, 5), 0);
+        assert_eq!(bucket(100, 0, 100, 5), 4);
+        assert_eq!(bucket(50, 0, 100, 5), 2);
+        // rounds to the nearest bucket
+        assert_eq!(bucket(12, 0, 100, 5), 0);
+        assert_eq!(bucket(13, 0, 100, 5), 1);
+        assert_eq!(bucket(-10, -10, 10, 3), 0);
+        // a point range or a single bucket has nowhere else to go
+        assert_eq!(bucket(7, 7, 7, 10), 0);
+        assert_eq!(bucket(9, 0, 10, 1), 0);
+    }
+
+    #[test]
+    fn braille_char_encodes_dot_bits() {
+        assert_eq!(braille_char(0), '\u{2800}');
+        assert_eq!(braille_char(0xff), '\u{28ff}');
+        let mut canvas = BrailleCanvas::new(1, 1);
+        canvas.set_dot(0, 0);
+        canvas.set_dot(1, 3);
+        assert_eq!(canvas.render(), "\u{2881}\n");
+    }
+
+    #[test]
+    fn braille_snapshot_of_example_2() {
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let (wire1, wire2) = (wire(line1), wire(line2));
+        let points: Vec<Point> = all_intersections(&wire1, &wire2).iter().map(|i| i.point).filter(|p| *p != Point::ORIGIN).collect();
+        let expected = concat!(
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡏⠉⠉⠉⠉⢹⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡤⠤⠤⠤⠤⠤⠤⠤⠤⠤⠤⠇⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⡤⠤⡄⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠸⠤⠤⠤⠤⠤⠤⠤●⢤⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⢸⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⢸⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⢸⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⢸⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡗●⡗⠒⠒⠒⠒⠒⠒⠒⠒⠒⡆⠀⠀⠀\n",
+            "⣇⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠓●⡗⠒⠒⠒⠒⠒⠒⠒⠒⠒⠃⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠘●⠒⠒⠒⠒⠒⠒⠒⠒⠒⠒⠒⠒⠒\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+            "⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢸⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⣀⡇⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀\n",
+        );
+        assert_eq!(braille(&wire1, &wire2, &points, 40, Some(20)), expected);
+    }
+
+    #[test]
+    fn cost_bin_covers_the_gradient() {
+        assert_eq!(cost_bin(30, 30, 40, 10), 0);
+        assert_eq!(cost_bin(40, 30, 40, 10), 9);
+        assert_eq!(cost_bin(35, 30, 40, 10), 4);
+        // every bin is reachable and the order is kept
+        let bins: Vec<usize> = (0..10).map(|c| cost_bin(c, 0, 9, 10)).collect();
+        assert_eq!(bins, (0..10).collect::<Vec<usize>>());
+        // one cost only, e.g. a single crossing
+        assert_eq!(cost_bin(5, 5, 5, 10), 0);
+    }
+
+    #[test]
+    fn legend_shows_the_gradient_between_the_costs() {
+        assert_eq!(legend(30, 40, false), "steps 30 .:-=+*o#%@ 40\n");
+        assert!(legend(30, 40, true).contains("\x1b[38;5;21m"));
+    }
+
+    #[test]
+    fn heatmap_snapshot_of_example_1() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let (wire1, wire2) = (wire(line1), wire(line2));
+        let intersections: Vec<PointWithCost> = all_intersections(&wire1, &wire2).into_iter().filter(|i| i.point != Point::ORIGIN).collect();
+        // the cheapest crossing, (6,5) at 30 steps, is the coldest and (3,3) at 40 the hottest
+        let expected = concat!(
+            "⡏⠉⠉⠉⠉⠉⠉⡇⠀⠀\n",
+            "⡇⠀⠀⢰⠒⠒⠒.⠒⢲\n",
+            "⡇⠀⢀@⣀⣀⣀⡇⠀⢸\n",
+            "⡇⠀⠀⠸⠀⠀⠀⠀⠀⢸\n",
+            "⣇⣀⣀⣀⣀⣀⣀⣀⣀⣸\n",
+            "steps 30 .:-=+*o#%@ 40\n",
+        );
+        assert_eq!(heatmap(&wire1, &wire2, &intersections, 10, Some(5), false), expected);
+    }
+
+    #[test]
+    fn pgm_pixels_of_a_corner() {
+        // one bucket per unit: wire 1 along the bottom row, wire 2 up the left column
+        let (wire1, wire2) = (wire("R4"), wire("U4"));
+        let image = pgm(&wire1, &wire2, 5);
+        let header = b"P5\n5 5\n255\n";
+        assert_eq!(&image[..header.len()], &header[..]);
+        let expected: Vec<u8> = vec!(
+            128, 0, 0, 0, 0,
+            128, 0, 0, 0, 0,
+            128, 0, 0, 0, 0,
+            128, 0, 0, 0, 0,
+            255, 128, 128, 128, 128,
+        );
+        assert_eq!(&image[header.len()..], &expected[..]);
+        // fewer buckets than units: both wires meet in the bottom left one
+        assert_eq!(raster(&wire1, &wire2, &bounds(&[&wire1, &wire2]), 2), vec!(1, 0, 2, 1));
+    }
+}
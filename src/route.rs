@@ -0,0 +1,214 @@
+use std::cmp::{max, min, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{all_intersections, contains, distance, Axis, Point, Segment};
+
+// a stretch of one wire between two neighbouring nodes, from the lower numbered node
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub length: i64,
+    // 0 for the first wire, 1 for the second; where the wires overlap each has its own edge
+    pub wire: usize,
+}
+
+// the wires as one graph: nodes at every segment end and every point where two segments meet,
+// sorted by x then y, edges between consecutive nodes along a segment
+#[derive(Debug, Clone)]
+pub struct Graph {
+    pub nodes: Vec<Point>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    // extra points become nodes too, splitting the segments they are on
+    fn new(wires: [&[Segment]; 2], extra: &[Point]) -> Graph {
+        let segments: Vec<Segment> = wires[0].iter().chain(wires[1].iter()).copied().collect();
+        let mut nodes: Vec<Point> = segments.iter().flat_map(|s| vec![s.end1, s.end2]).collect();
+        // a segment against itself only yields its ends, which are already nodes
+        nodes.extend(all_intersections(&segments, &segments).iter().map(|i| i.point));
+        nodes.extend_from_slice(extra);
+        nodes.sort_unstable_by_key(|p| (p.x(), p.y()));
+        nodes.dedup();
+        let index: HashMap<Point, usize> = nodes.iter().enumerate().map(|(n, p)| (*p, n)).collect();
+        let mut edges: Vec<Edge> = vec!();
+        for (wire, segments) in wires.iter().enumerate() {
+            for s in segments.iter() {
+                // normalized, so end1 has the lowest coordinate along the segment
+                let axis = Axis::of(s);
+                let mut on: Vec<Point> = nodes.iter().filter(|p| contains(s, **p)).copied().collect();
+                on.sort_unstable_by_key(|p| axis.along(*p));
+                for pair in on.windows(2) {
+                    let (a, b) = (index[&pair[0]], index[&pair[1]]);
+                    edges.push(Edge { from: min(a, b), to: max(a, b), length: distance(pair[0], pair[1]) as i64, wire });
+                }
+            }
+        }
+        // a wire running over itself would add the same stretch twice
+        edges.sort_unstable_by_key(|e| (e.from, e.to, e.wire));
+        edges.dedup();
+        Graph { nodes, edges }
+    }
+
+    // per node, its neighbours and the length of the edge to each
+    fn adjacency(&self) -> Vec<Vec<(usize, i64)>> {
+        let mut adjacent: Vec<Vec<(usize, i64)>> = vec![vec!(); self.nodes.len()];
+        for e in &self.edges {
+            adjacent[e.from].push((e.to, e.length));
+            adjacent[e.to].push((e.from, e.length));
+        }
+        adjacent
+    }
+
+    // one edge per line: "x1,y1 x2,y2 length wire" with the wire 1-based
+    pub fn edge_list(&self) -> String {
+        let mut out = String::new();
+        for e in &self.edges {
+            let (a, b) = (self.nodes[e.from], self.nodes[e.to]);
+            out += &format!("{},{} {},{} {} {}\n", a.x(), a.y(), b.x(), b.y(), e.length, e.wire + 1);
+        }
+        out
+    }
+}
+
+// the graph of both wires as route walks it
+pub fn wire_graph(wire1: &[Segment], wire2: &[Segment]) -> Graph {
+    Graph::new([wire1, wire2], &[])
+}
+
+// the shortest way from one point to another moving only along the wires, switching wires where
+// they meet, as its length and the nodes passed. None unless both points are on a wire and
+// connected
+pub fn route(wires: [&[Segment]; 2], from: Point, to: Point) -> Option<(i64, Vec<Point>)> {
+    let on_wires = |p: Point| wires.iter().any(|w| w.iter().any(|s| contains(s, p)));
+    if !on_wires(from) || !on_wires(to) {
+        return None;
+    }
+    let graph = Graph::new(wires, &[from, to]);
+    let adjacent = graph.adjacency();
+    let start = graph.nodes.iter().position(|p| *p == from)?;
+    let goal = graph.nodes.iter().position(|p| *p == to)?;
+    let mut best: Vec<Option<i64>> = vec![None; graph.nodes.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; graph.nodes.len()];
+    let mut queue = BinaryHeap::new();
+    best[start] = Some(0);
+    queue.push(Reverse((0, start)));
+    while let Some(Reverse((length, node))) = queue.pop() {
+        if node == goal {
+            let mut path = vec![graph.nodes[goal]];
+            let mut at = goal;
+            while let Some(p) = previous[at] {
+                path.push(graph.nodes[p]);
+                at = p;
+            }
+            path.reverse();
+            return Some((length, path));
+        }
+        if best[node].is_some_and(|b| length > b) {
+            continue;
+        }
+        for &(next, edge) in &adjacent[node] {
+            let through = length + edge;
+            if best[next].is_none_or(|b| through < b) {
+                best[next] = Some(through);
+                previous[next] = Some(node);
+                queue.push(Reverse((through, next)));
+            }
+        }
+    }
+    None
+}
+
+fn find(parent: &mut [usize], n: usize) -> usize {
+    let mut root = n;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[n] = root;
+    root
+}
+
+// the bounded regions the two wires enclose together, the unbounded outside not counted. Euler's
+// formula V - E + F = 1 + C over the graph's C connected parts, with a stretch both wires run
+// along counted as one edge
+pub fn region_count(wire1: &[Segment], wire2: &[Segment]) -> usize {
+    let graph = wire_graph(wire1, wire2);
+    let mut stretches: Vec<(usize, usize)> = graph.edges.iter().map(|e| (e.from, e.to)).collect();
+    stretches.dedup();
+    let mut parent: Vec<usize> = (0..graph.nodes.len()).collect();
+    for &(a, b) in &stretches {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        parent[ra] = rb;
+    }
+    let components = (0..graph.nodes.len()).filter(|&n| find(&mut parent, n) == n).count();
+    (stretches.len() + components).saturating_sub(graph.nodes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{wire, wire_at, EXAMPLES};
+
+    #[test]
+    fn routes_along_the_wires() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let (path1, path2) = (wire(line1), wire(line2));
+        let p = Point::new;
+        // up wire 1's U5
+        assert_eq!(route([&path1, &path2], p(8, 0), p(8, 5)), Some((5, vec!(p(8, 0), p(8, 5)))));
+        // from a corner of wire 2 onto wire 1 at the crossing (6,5), half the length of going round by the origin
+        assert_eq!(route([&path1, &path2], p(0, 7), p(8, 5)), Some((10, vec!(p(0, 7), p(6, 7), p(6, 5), p(8, 5)))));
+        // mid segment points become nodes of their own
+        assert_eq!(route([&path1, &path2], p(4, 0), p(0, 2)), Some((6, vec!(p(4, 0), p(0, 0), p(0, 2)))));
+        assert_eq!(route([&path1, &path2], p(4, 0), p(4, 0)), Some((0, vec!(p(4, 0)))));
+    }
+
+    #[test]
+    fn no_route_between_apart_wires() {
+        let (apart1, apart2) = (wire("R3"), wire_at("R3", Point::new(0, 5)));
+        assert_eq!(route([&apart1, &apart2], Point::new(1, 0), Point::new(2, 5)), None);
+        // off both wires
+        assert_eq!(route([&apart1, &apart2], Point::new(1, 0), Point::new(1, 1)), None);
+        assert!(route([&apart1, &apart2], Point::new(3, 0), Point::new(0, 0)).is_some());
+    }
+
+    #[test]
+    fn graph_of_example_1() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let graph = wire_graph(&wire(line1), &wire(line2));
+        // 9 corners and ends besides the shared origin, and the crossings (3,3) and (6,5)
+        assert_eq!(graph.nodes.len(), 11);
+        // each wire's segments split once at each crossing on them
+        assert_eq!(graph.edges.len(), 12);
+        assert_eq!(graph.edges.iter().filter(|e| e.wire == 0).count(), 6);
+        let neighbours = |p: Point| -> Vec<Point> {
+            let n = graph.nodes.iter().position(|q| *q == p).unwrap();
+            let mut found: Vec<Point> = graph.edges.iter()
+                .filter_map(|e| if e.from == n { Some(graph.nodes[e.to]) } else if e.to == n { Some(graph.nodes[e.from]) } else { None })
+                .collect();
+            found.sort_unstable_by_key(|p| (p.x(), p.y()));
+            found
+        };
+        assert_eq!(neighbours(Point::new(6, 5)), vec!(Point::new(3, 5), Point::new(6, 3), Point::new(6, 7), Point::new(8, 5)));
+        assert_eq!(neighbours(Point::ORIGIN), vec!(Point::new(0, 7), Point::new(8, 0)));
+        assert!(graph.edge_list().starts_with("0,0 0,7 7 2\n0,0 8,0 8 1\n"));
+    }
+
+    #[test]
+    fn regions_between_the_wires() {
+        // two straight wires crossing cut the plane in four, but none of it is bounded
+        assert_eq!(region_count(&wire_at("R4", Point::new(-2, 0)), &wire_at("U4", Point::new(0, -2))), 0);
+        // the example's wires close the square (3,3) to (6,5) and the rest of the outline around it
+        let (line1, line2, _, _) = EXAMPLES[0];
+        assert_eq!(region_count(&wire(line1), &wire(line2)), 2);
+        // apart from each other
+        assert_eq!(region_count(&wire("R3,U3"), &wire_at("L3,D3", Point::new(-1, -1))), 0);
+        // a loop and a wire far from it, so two connected parts
+        assert_eq!(region_count(&wire("R10,U10,L10,D10"), &wire_at("R2", Point::new(20, 20))), 1);
+        // two overlapping squares: each corner on its own and the middle
+        assert_eq!(region_count(&wire("R4,U4,L4,D4"), &wire_at("R4,U4,L4,D4", Point::new(2, 2))), 3);
+        // a loop walked by both wires is still one region
+        assert_eq!(region_count(&wire("R4,U4,L4,D4"), &wire("U4,R4,D4,L4")), 1);
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use crate::wire::PreparedWire;
+use crate::{distance, normalize, path_to_segments, Distance, PathSegment, Point, PointWithCost, Segment, Solution, Steps};
+
+// edits kept for undo; the oldest are dropped first
+const HISTORY_LIMIT: usize = 100;
+
+// a change to one wire, given by index
+#[derive(Copy, Debug, Clone)]
+pub enum Edit {
+    Append(usize, PathSegment),
+    Pop(usize),
+    // wire, move index and its new value
+    Replace(usize, usize, PathSegment),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    NoMoves { wire: usize },
+    OutOfRange { wire: usize, index: usize, moves: usize },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::NoMoves { wire } => write!(f, "wire {} has no moves", wire + 1),
+            EditError::OutOfRange { wire, index, moves } => write!(f, "move {} is outside wire {}, which has {} moves", index + 1, wire + 1, moves),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+// a cost with the point's coordinates, ordering intersections of equal cost
+type Key<C> = (C, i32, i32);
+
+// an intersection found when one segment was added, with the other wire's segment, known by the
+// steps at which it starts
+#[derive(Copy, Debug, Clone)]
+struct Hit {
+    other: i32,
+    found: PointWithCost,
+}
+
+// a pair of wires edited one move at a time, answering both parts after every edit without solving
+// again from scratch. Moves are added and removed at the far ends; apply also replaces moves
+// anywhere, rebuilding what lies after them only once something needs it, and keeps a history
+// for undo and redo
+pub struct Session {
+    origin: Point,
+    moves: [Vec<PathSegment>; 2],
+    // per move, whether it added a segment; zero length moves add none
+    move_segments: [Vec<bool>; 2],
+    segments: [Vec<Segment>; 2],
+    // where each wire ends and after how many steps
+    ends: [(Point, i32); 2],
+    prepared: [PreparedWire; 2],
+    // per segment, the intersections found when it was added; a pair of segments is only ever
+    // recorded under the later of the two
+    hits: [Vec<Vec<Hit>>; 2],
+    // the first replaced move of each wire whose segments and hits, and those of every move after
+    // it, are still the ones from before the replace
+    dirty: [Option<usize>; 2],
+    // multisets of the recorded intersections, keyed by part 1 and part 2 cost
+    by_distance: BTreeMap<Key<Distance>, usize>,
+    by_steps: BTreeMap<Key<Steps>, usize>,
+    // each applied edit with the one reverting it
+    undo: VecDeque<(Edit, Edit)>,
+    redo: Vec<(Edit, Edit)>,
+}
+
+fn add<K: Ord>(counts: &mut BTreeMap<K, usize>, key: K) {
+    *counts.entry(key).or_insert(0) += 1;
+}
+
+fn take<K: Ord>(counts: &mut BTreeMap<K, usize>, key: K) {
+    if let Some(count) = counts.get_mut(&key) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(&key);
+        }
+    }
+}
+
+impl Session {
+    pub fn new(origin: Point) -> Session {
+        Session {
+            origin,
+            moves: [vec!(), vec!()],
+            move_segments: [vec!(), vec!()],
+            segments: [vec!(), vec!()],
+            ends: [(origin, 0); 2],
+            prepared: [PreparedWire::new(&[]), PreparedWire::new(&[])],
+            hits: [vec!(), vec!()],
+            dirty: [None; 2],
+            by_distance: BTreeMap::new(),
+            by_steps: BTreeMap::new(),
+            undo: VecDeque::new(),
+            redo: vec!(),
+        }
+    }
+
+    pub fn from_moves(origin: Point, moves: [&[PathSegment]; 2]) -> Session {
+        let mut session = Session::new(origin);
+        for (wire_id, wire) in moves.iter().enumerate() {
+            for step in wire.iter() {
+                session.append_move(wire_id, *step);
+            }
+        }
+        session
+    }
+
+    // the normalized segments of a wire, as Wire::new would build them; only the tests look
+    #[cfg(test)]
+    pub fn segments(&mut self, wire_id: usize) -> &[Segment] {
+        self.rebuild();
+        &self.segments[wire_id]
+    }
+
+    // as solve counts them: part 1 leaves out the origin, part 2 only the start of both wires
+    fn keys(&self, found: &PointWithCost) -> (Option<Key<Distance>>, Option<Key<Steps>>) {
+        let p = found.point;
+        let by_distance = if p != self.origin { Some((Distance(distance(p, self.origin)), p.x(), p.y())) } else { None };
+        let by_steps = if found.cost > Steps(0) { Some((found.cost, p.x(), p.y())) } else { None };
+        (by_distance, by_steps)
+    }
+
+    fn record(&mut self, found: &PointWithCost) {
+        let (d, s) = self.keys(found);
+        if let Some(d) = d {
+            add(&mut self.by_distance, d);
+        }
+        if let Some(s) = s {
+            add(&mut self.by_steps, s);
+        }
+    }
+
+    fn forget(&mut self, found: &PointWithCost) {
+        let (d, s) = self.keys(found);
+        if let Some(d) = d {
+            take(&mut self.by_distance, d);
+        }
+        if let Some(s) = s {
+            take(&mut self.by_steps, s);
+        }
+    }
+
+    pub fn append_move(&mut self, wire_id: usize, step: PathSegment) {
+        self.rebuild();
+        self.push_move(wire_id, step);
+    }
+
+    pub fn pop_move(&mut self, wire_id: usize) -> Option<PathSegment> {
+        self.rebuild();
+        self.pop_last(wire_id)
+    }
+
+    // append_move, with everything before it up to date
+    fn push_move(&mut self, wire_id: usize, step: PathSegment) {
+        let (at, steps) = self.ends[wire_id];
+        self.moves[wire_id].push(step);
+        let segment = match path_to_segments(vec![step], at).pop() {
+            Some(s) => normalize(vec![Segment { steps, ..s }])[0],
+            None => {
+                self.move_segments[wire_id].push(false);
+                return;
+            }
+        };
+        let other = 1 - wire_id;
+        let hits: Vec<Hit> = self.prepared[other].intersections_with(segment).into_iter()
+            .map(|(other, found)| Hit { other, found })
+            .collect();
+        for hit in &hits {
+            self.record(&hit.found);
+        }
+        self.move_segments[wire_id].push(true);
+        self.segments[wire_id].push(segment);
+        self.prepared[wire_id].insert(segment);
+        self.hits[wire_id].push(hits);
+        self.ends[wire_id] = (segment.end(), steps + step.distance);
+    }
+
+    // pop_move, with everything up to date
+    fn pop_last(&mut self, wire_id: usize) -> Option<PathSegment> {
+        let step = self.moves[wire_id].pop()?;
+        if self.move_segments[wire_id].pop() == Some(false) {
+            return Some(step);
+        }
+        let segment = self.segments[wire_id].pop().expect("a segment per non-empty move");
+        self.prepared[wire_id].remove(&segment);
+        for hit in self.hits[wire_id].pop().unwrap_or_default() {
+            self.forget(&hit.found);
+        }
+        // pairs with segments the other wire gained after this one was added
+        let other = 1 - wire_id;
+        let mut later: Vec<PointWithCost> = vec!();
+        for hits in self.hits[other].iter_mut() {
+            later.extend(hits.iter().filter(|h| h.other == segment.steps).map(|h| h.found));
+            hits.retain(|h| h.other != segment.steps);
+        }
+        for found in &later {
+            self.forget(found);
+        }
+        self.ends[wire_id] = (segment.start, segment.steps);
+        Some(step)
+    }
+
+    // both answers, None until the wires cross away from the origin. Ties go to the
+    // smallest x, then y, which may differ from the point solve reports
+    pub fn solution(&mut self) -> Option<Solution> {
+        self.rebuild();
+        let (&(distance, dx, dy), _) = self.by_distance.iter().next()?;
+        let (&(steps, sx, sy), _) = self.by_steps.iter().next()?;
+        Some(Solution { distance, distance_at: Point::new(dx, dy), steps, steps_at: Point::new(sx, sy) })
+    }
+
+    // everything from index on is walked from a different place afterwards. Only the move is
+    // swapped here; the tail is rebuilt once, by the next call that needs it, however many replaces
+    // come before that
+    fn replace_move(&mut self, wire_id: usize, index: usize, step: PathSegment) -> PathSegment {
+        self.dirty[wire_id] = Some(self.dirty[wire_id].map_or(index, |d| d.min(index)));
+        std::mem::replace(&mut self.moves[wire_id][index], step)
+    }
+
+    // pops the segments of each wire's stale tail and appends its moves again as they now read
+    fn rebuild(&mut self) {
+        for wire_id in 0..2 {
+            if let Some(index) = self.dirty[wire_id].take() {
+                let tail = self.moves[wire_id][index..].to_vec();
+                while self.moves[wire_id].len() > index {
+                    self.pop_last(wire_id);
+                }
+                for step in tail {
+                    self.push_move(wire_id, step);
+                }
+            }
+        }
+    }
+
+    // applies edit and returns the edit undoing it
+    fn perform(&mut self, edit: Edit) -> Result<Edit, EditError> {
+        match edit {
+            Edit::Append(wire, step) => {
+                self.append_move(wire, step);
+                Ok(Edit::Pop(wire))
+            }
+            Edit::Pop(wire) => {
+                self.pop_move(wire).map(|step| Edit::Append(wire, step)).ok_or(EditError::NoMoves { wire })
+            }
+            Edit::Replace(wire, index, step) => {
+                let moves = self.moves[wire].len();
+                if index >= moves {
+                    return Err(EditError::OutOfRange { wire, index, moves });
+                }
+                Ok(Edit::Replace(wire, index, self.replace_move(wire, index, step)))
+            }
+        }
+    }
+
+    pub fn apply(&mut self, edit: Edit) -> Result<(), EditError> {
+        let inverse = self.perform(edit)?;
+        if self.undo.len() == HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back((edit, inverse));
+        self.redo.clear();
+        Ok(())
+    }
+
+    // false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop_back() {
+            Some((edit, inverse)) => {
+                self.perform(inverse).expect("an inverse always applies");
+                self.redo.push((edit, inverse));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some((edit, inverse)) => {
+                self.perform(edit).expect("a redone edit always applies");
+                self.undo.push_back((edit, inverse));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+    use crate::solve;
+    use crate::tests::{moves, EXAMPLES};
+    use crate::wire::Wire;
+
+    const DIRECTIONS: [char; 4] = ['U', 'D', 'L', 'R'];
+
+    // short moves, so the wires keep running into each other
+    fn random_move(rng: &mut Rng) -> PathSegment {
+        PathSegment { direction: DIRECTIONS[rng.below(4) as usize], distance: rng.below(12) as i32 }
+    }
+
+    // both answers solving the session's moves from scratch
+    fn from_scratch(session: &Session) -> Option<(Distance, Steps)> {
+        let wire = |id: usize| Wire::new(session.moves[id].clone(), session.origin).segments;
+        solve(wire(0), wire(1), session.origin).map(|s| (s.distance, s.steps))
+    }
+
+    fn answers(session: &mut Session) -> Option<(Distance, Steps)> {
+        session.solution().map(|s| (s.distance, s.steps))
+    }
+
+    #[test]
+    fn appending_and_popping_match_solving_from_scratch() {
+        for seed in 0..5 {
+            let mut rng = Rng::new(seed);
+            let mut session = Session::new(Point::new(3, -2));
+            for _ in 0..300 {
+                let wire_id = rng.below(2) as usize;
+                // grows more often than it shrinks
+                if rng.below(3) == 0 {
+                    session.pop_move(wire_id);
+                } else {
+                    session.append_move(wire_id, random_move(&mut rng));
+                }
+                assert_eq!(answers(&mut session), from_scratch(&session), "seed {}", seed);
+            }
+            for wire_id in 0..2 {
+                let expected = Wire::new(session.moves[wire_id].clone(), session.origin).segments;
+                let ends = |segments: &[Segment]| -> Vec<(Point, Point, i32)> { segments.iter().map(|s| (s.end1, s.end2, s.steps)).collect() };
+                assert_eq!(ends(session.segments(wire_id)), ends(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn popping_everything_leaves_no_answer() {
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let mut session = Session::from_moves(Point::ORIGIN, [&moves(line1), &moves(line2)]);
+        assert_eq!(answers(&mut session), Some((Distance(6), Steps(30))));
+        while session.pop_move(0).is_some() {}
+        assert_eq!(answers(&mut session), None);
+        assert_eq!(session.pop_move(0), None);
+    }
+
+    #[test]
+    fn undoing_a_script_gives_the_original_answers() {
+        for (line1, line2, distance, steps) in EXAMPLES.iter() {
+            let original = Some((Distance(*distance), Steps(*steps)));
+            let (moves1, moves2) = (moves(line1), moves(line2));
+            let mut session = Session::from_moves(Point::ORIGIN, [&moves1, &moves2]);
+            let script = [
+                Edit::Replace(0, 1, PathSegment { direction: 'D', distance: 40 }),
+                Edit::Append(1, PathSegment { direction: 'L', distance: 300 }),
+                Edit::Replace(1, 0, PathSegment { direction: 'R', distance: 7 }),
+                Edit::Pop(0),
+                Edit::Replace(0, 0, PathSegment { direction: 'U', distance: 0 }),
+            ];
+            let mut after: Vec<Option<(Distance, Steps)>> = vec!();
+            for edit in script.iter() {
+                session.apply(*edit).unwrap();
+                assert_eq!(answers(&mut session), from_scratch(&session));
+                after.push(answers(&mut session));
+            }
+            // back to the start, then forward again to each edit in turn
+            while session.undo() {
+                assert_eq!(answers(&mut session), from_scratch(&session));
+            }
+            assert_eq!((&session.moves[0], &session.moves[1]), (&moves1, &moves2));
+            assert_eq!(answers(&mut session), original);
+            for expected in after {
+                assert!(session.redo());
+                assert_eq!(answers(&mut session), expected);
+            }
+            assert!(!session.redo());
+            while session.undo() {}
+            assert_eq!(answers(&mut session), original);
+        }
+    }
+
+    #[test]
+    fn bad_edits_change_nothing() {
+        let mut session = Session::from_moves(Point::ORIGIN, [&moves("R8,U5,L5,D3"), &[]]);
+        assert_eq!(session.apply(Edit::Pop(1)), Err(EditError::NoMoves { wire: 1 }));
+        let step = PathSegment { direction: 'U', distance: 1 };
+        assert_eq!(session.apply(Edit::Replace(0, 4, step)), Err(EditError::OutOfRange { wire: 0, index: 4, moves: 4 }));
+        assert!(!session.undo());
+        assert_eq!(EditError::OutOfRange { wire: 0, index: 4, moves: 4 }.to_string(), "move 5 is outside wire 1, which has 4 moves");
+    }
+}
@@ -0,0 +1,292 @@
+use std::convert::From;
+
+use crate::route::region_count;
+use crate::wire::{TurnStats, Wire};
+use crate::{all_intersections, distance, distinct_except, reverses, shared_start, turn_count, Point, PointWithCost, Rect};
+
+// in the order they are reported
+const DIRECTIONS: [(char, &str); 4] = [('L', "left"), ('R', "right"), ('U', "up"), ('D', "down")];
+
+pub struct WireStats {
+    // see Wire::fingerprint
+    pub fingerprint: u64,
+    pub moves: usize,
+    // after merging consecutive moves in the same direction
+    pub segments: usize,
+    pub length: i64,
+    // how much shorter Wire::simplified is, the wire that could be saved
+    pub doubled_back: i64,
+    pub bounding_box: Rect,
+    // left, right, up, down
+    pub directions: [usize; 4],
+    pub longest_move: Option<i32>,
+    pub shortest_move: Option<i32>,
+    pub turns: TurnStats,
+    // net quarter turns, see Wire::winding
+    pub winding: i32,
+    // switches between horizontal and vertical
+    pub turn_count: usize,
+    pub reverses: bool,
+    pub self_crossings: usize,
+    // None unless the wire ends back at its origin, see Wire::enclosed_area
+    pub enclosed_area: Option<i64>,
+    pub enclosed_points: Option<i64>,
+    pub simple_loop: bool,
+    // corners of the convex hull and the area inside it
+    pub hull_corners: usize,
+    pub hull_area: f64,
+    // see Wire::find_rectangles
+    pub rectangles: Vec<Rect>,
+}
+
+// aggregates over distinct intersections, a start both wires share excluded
+#[derive(Copy, Debug, Clone, PartialEq)]
+pub struct IntersectionStats {
+    pub count: usize,
+    // Manhattan distance from the reference point: min, median, max
+    pub distance: Option<(i32, i32, i32)>,
+    pub mean_distance: Option<f64>,
+    // combined steps of both wires: min, median, max
+    pub steps: Option<(i32, i32, i32)>,
+    pub mean_steps: Option<f64>,
+    // ties go to the smaller x, then y
+    pub farthest: Option<Point>,
+}
+
+pub struct Stats {
+    pub wires: [WireStats; 2],
+    pub bounding_box: Rect,
+    pub intersections: IntersectionStats,
+    // bounded regions the two wires cut the plane into, see region_count; only counted on request,
+    // as that is quadratic in the segments
+    pub regions: Option<usize>,
+}
+
+pub fn wire_stats(wire: &Wire) -> WireStats {
+    let mut directions = [0; 4];
+    for (count, (d, _)) in directions.iter_mut().zip(DIRECTIONS.iter()) {
+        *count = wire.count_moves(*d);
+    }
+    WireStats {
+        fingerprint: wire.fingerprint(),
+        moves: wire.moves.len(),
+        segments: wire.merged_segments().len(),
+        length: wire.total_length(),
+        doubled_back: wire.total_length() - wire.simplified().total_length(),
+        bounding_box: wire.bounding_box(),
+        directions,
+        longest_move: wire.longest_move(),
+        shortest_move: wire.shortest_move(),
+        turns: wire.turns(),
+        winding: wire.winding(),
+        turn_count: turn_count(&wire.segments),
+        reverses: reverses(&wire.segments),
+        self_crossings: wire.self_crossings(),
+        enclosed_area: wire.enclosed_area(),
+        enclosed_points: wire.enclosed_area_including_boundary(),
+        simple_loop: wire.is_simple_loop(),
+        hull_corners: wire.convex_hull().len(),
+        hull_area: wire.hull_area(),
+        rectangles: wire.find_rectangles().into_iter().map(|(rect, _)| rect).collect(),
+    }
+}
+
+// the median of an even count is the lower of the two middle values
+fn min_median_max(sorted: &[i32]) -> Option<(i32, i32, i32)> {
+    if sorted.is_empty() {
+        return None;
+    }
+    Some((sorted[0], sorted[(sorted.len() - 1) / 2], sorted[sorted.len() - 1]))
+}
+
+fn mean(values: &[i32]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64)
+}
+
+impl IntersectionStats {
+    // duplicates are merged first, so raw all_intersections output can be passed in
+    pub fn new(intersections: &[PointWithCost], start: Option<Point>, reference: Point) -> IntersectionStats {
+        let distinct = distinct_except(intersections, start);
+        let mut distances: Vec<i32> = distinct.iter().map(|i| distance(i.point, reference)).collect();
+        let mut steps: Vec<i32> = distinct.iter().map(|i| i.cost.0).collect();
+        distances.sort();
+        steps.sort();
+        let farthest = distinct.iter().map(|i| i.point)
+            .max_by_key(|p| (distance(*p, reference), -p.x(), -p.y()));
+        IntersectionStats {
+            count: distinct.len(),
+            distance: min_median_max(&distances),
+            mean_distance: mean(&distances),
+            steps: min_median_max(&steps),
+            mean_steps: mean(&steps),
+            farthest,
+        }
+    }
+}
+
+// both wires starting at (0,0), distances measured from there
+impl From<&[PointWithCost]> for IntersectionStats {
+    fn from(intersections: &[PointWithCost]) -> IntersectionStats {
+        IntersectionStats::new(intersections, Some(Point::ORIGIN), Point::ORIGIN)
+    }
+}
+
+// part 1 distances are measured from reference
+pub fn stats(wires: &[Wire; 2], reference: Point, count_regions: bool) -> Stats {
+    let (merged_0, merged_1) = (wires[0].merged_segments(), wires[1].merged_segments());
+    let all = all_intersections(&merged_0, &merged_1);
+    let wire_stats = [wire_stats(&wires[0]), wire_stats(&wires[1])];
+    let bounding_box = wire_stats[0].bounding_box.union(&wire_stats[1].bounding_box);
+    let regions = count_regions.then(|| region_count(&wires[0].segments, &wires[1].segments));
+    Stats { wires: wire_stats, bounding_box, intersections: IntersectionStats::new(&all, shared_start(&merged_0, &merged_1), reference), regions }
+}
+
+fn or_dash(value: Option<i32>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+pub fn text(stats: &Stats) -> String {
+    let mut out = String::new();
+    for (n, wire) in stats.wires.iter().enumerate() {
+        out += &format!("wire {}: {} moves, {} segments, length {}\n", n + 1, wire.moves, wire.segments, wire.length);
+        out += &format!("  fingerprint: {:016x}\n", wire.fingerprint);
+        out += &format!("  bounding box {} to {}\n", wire.bounding_box.min, wire.bounding_box.max);
+        let counts: Vec<String> = DIRECTIONS.iter().zip(wire.directions.iter())
+            .map(|((_, name), count)| format!("{} {}", count, name))
+            .collect();
+        out += &format!("  moves: {}\n", counts.join(", "));
+        out += &format!("  longest move {}, shortest move {}\n", or_dash(wire.longest_move), or_dash(wire.shortest_move));
+        let turns = wire.turns;
+        out += &format!("  turns: {} left, {} right, {} straight, {} reversals, winding {}\n", turns.left, turns.right, turns.straight, turns.reversals, wire.winding);
+        out += &format!("  turn count: {}{}\n", wire.turn_count, if wire.reverses { ", doubles back on itself" } else { "" });
+        out += &format!("  self-crossings: {}\n", wire.self_crossings);
+        if wire.doubled_back > 0 {
+            out += &format!("  doubled back: {} of the length cancels out\n", wire.doubled_back);
+        }
+        if let (Some(area), Some(points)) = (wire.enclosed_area, wire.enclosed_points) {
+            let simple = if wire.simple_loop { ", a simple loop" } else { "" };
+            out += &format!("  closed: area {}, {} points with the boundary{}\n", area, points, simple);
+        }
+        out += &format!("  convex hull: {} corners, area {}\n", wire.hull_corners, wire.hull_area);
+        if !wire.rectangles.is_empty() {
+            let rects: Vec<String> = wire.rectangles.iter().map(|r| format!("{} to {}", r.min, r.max)).collect();
+            out += &format!("  rectangles: {}\n", rects.join(", "));
+        }
+    }
+    out += &format!("bounding box {} to {}\n", stats.bounding_box.min, stats.bounding_box.max);
+    if let Some(regions) = stats.regions {
+        out += &format!("regions: {}\n", regions);
+    }
+    let i = &stats.intersections;
+    out += &format!("intersections: {}\n", i.count);
+    if let (Some((min, median, max)), Some(mean)) = (i.distance, i.mean_distance) {
+        out += &format!("  distance: min {}, median {}, mean {:.1}, max {}\n", min, median, mean, max);
+    }
+    if let (Some((min, median, max)), Some(mean)) = (i.steps, i.mean_steps) {
+        out += &format!("  steps: min {}, median {}, mean {:.1}, max {}\n", min, median, mean, max);
+    }
+    if let Some(p) = i.farthest {
+        out += &format!("  farthest: {}\n", p);
+    }
+    out
+}
+
+fn json_option(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "null".to_string(), |v| v.to_string())
+}
+
+fn json_rect(rect: &Rect) -> String {
+    format!("{{\"min\":[{},{}],\"max\":[{},{}]}}", rect.min.x(), rect.min.y(), rect.max.x(), rect.max.y())
+}
+
+fn json_wire(wire: &WireStats) -> String {
+    let directions: Vec<String> = DIRECTIONS.iter().zip(wire.directions.iter())
+        .map(|((_, name), count)| format!("\"{}\":{}", name, count))
+        .collect();
+    let turns = format!("{{\"left\":{},\"right\":{},\"straight\":{},\"reversals\":{}}}",
+                        wire.turns.left, wire.turns.right, wire.turns.straight, wire.turns.reversals);
+    let rectangles: Vec<String> = wire.rectangles.iter().map(json_rect).collect();
+    // as a hex string, since a JSON number loses the low bits of a u64
+    format!("{{\"fingerprint\":\"{:016x}\",\"moves\":{},\"segments\":{},\"length\":{},\"doubled_back\":{},\"bounding_box\":{},\"directions\":{{{}}},\"longest_move\":{},\"shortest_move\":{},\"turns\":{},\"winding\":{},\"turn_count\":{},\"reverses\":{},\"self_crossings\":{},\"enclosed_area\":{},\"enclosed_points\":{},\"simple_loop\":{},\"hull\":{{\"corners\":{},\"area\":{}}},\"rectangles\":[{}]}}",
+            wire.fingerprint, wire.moves, wire.segments, wire.length, wire.doubled_back, json_rect(&wire.bounding_box), directions.join(","),
+            json_option(wire.longest_move), json_option(wire.shortest_move), turns, wire.winding, wire.turn_count, wire.reverses, wire.self_crossings,
+            json_option(wire.enclosed_area), json_option(wire.enclosed_points), wire.simple_loop,
+            wire.hull_corners, wire.hull_area, rectangles.join(","))
+}
+
+fn json_summary(summary: Option<(i32, i32, i32)>, mean: Option<f64>) -> String {
+    match (summary, mean) {
+        (Some((min, median, max)), Some(mean)) =>
+            format!("{{\"min\":{},\"median\":{},\"mean\":{},\"max\":{}}}", min, median, mean, max),
+        _ => "null".to_string(),
+    }
+}
+
+fn json_intersections(i: &IntersectionStats) -> String {
+    let farthest = i.farthest.map_or_else(|| "null".to_string(), |p| format!("[{},{}]", p.x(), p.y()));
+    format!("{{\"count\":{},\"distance\":{},\"steps\":{},\"farthest\":{}}}",
+            i.count, json_summary(i.distance, i.mean_distance), json_summary(i.steps, i.mean_steps), farthest)
+}
+
+pub fn json(stats: &Stats) -> String {
+    format!("{{\"wires\":[{},{}],\"bounding_box\":{},\"regions\":{},\"intersections\":{}}}",
+            json_wire(&stats.wires[0]), json_wire(&stats.wires[1]), json_rect(&stats.bounding_box), json_option(stats.regions),
+            json_intersections(&stats.intersections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{moves, wire, EXAMPLES};
+
+    #[test]
+    fn intersection_stats_of_example_2() {
+        let (line1, line2, _, _) = EXAMPLES[1];
+        let all = all_intersections(&wire(line1), &wire(line2));
+        // (155,4) 159 away after 726 steps, (155,11) 166 after 850, (158,-12) 170 after 610, (146,46) 192 after 624
+        let expected = IntersectionStats {
+            count: 4,
+            distance: Some((159, 166, 192)),
+            mean_distance: Some(171.75),
+            steps: Some((610, 624, 850)),
+            mean_steps: Some(702.5),
+            farthest: Some(Point::new(146, 46)),
+        };
+        assert_eq!(IntersectionStats::from(&all[..]), expected);
+        // every crossing found twice still counts once
+        let twice: Vec<PointWithCost> = all.iter().chain(all.iter()).cloned().collect();
+        assert_eq!(IntersectionStats::from(&twice[..]), expected);
+    }
+
+    #[test]
+    fn intersection_stats_without_crossings() {
+        let none = IntersectionStats::from(&all_intersections(&wire("R5,U5"), &wire("L5,D5"))[..]);
+        assert_eq!(none, IntersectionStats { count: 0, distance: None, mean_distance: None, steps: None, mean_steps: None, farthest: None });
+        // equally far, the smaller x wins
+        let tied = all_intersections(&wire("U2,L4,R8"), &wire("R3,U4,L6,D4"));
+        assert_eq!(IntersectionStats::from(&tied[..]).farthest, Some(Point::new(-3, 2)));
+    }
+
+    #[test]
+    fn stats_of_wires_with_different_starts() {
+        // the second wire passes through where the first starts, which is a crossing like any other
+        let wires = [Wire::new(moves("R8"), Point::ORIGIN), Wire::new(moves("U4,R4,D4"), Point::new(0, -2))];
+        // (0,0) 3 from (0,3) after 2 steps, (4,0) 7 away after 14
+        let expected = IntersectionStats {
+            count: 2,
+            distance: Some((3, 3, 7)),
+            mean_distance: Some(5.0),
+            steps: Some((2, 2, 14)),
+            mean_steps: Some(8.0),
+            farthest: Some(Point::new(4, 0)),
+        };
+        assert_eq!(stats(&wires, Point::new(0, 3), false).intersections, expected);
+        // a start both wires share still is not one
+        let (line1, line2, _, _) = EXAMPLES[0];
+        let shared = [Wire::new(moves(line1), Point::ORIGIN), Wire::new(moves(line2), Point::ORIGIN)];
+        assert_eq!(stats(&shared, Point::ORIGIN, false).intersections.count, 2);
+    }
+}
@@ -0,0 +1,22 @@
+use std::fmt;
+
+// steps walked along the wires, as counted for part 2; kept apart from Distance so the two cannot
+// be compared or mixed by mistake
+#[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Steps(pub i32);
+
+// manhattan distance between two points, as counted for part 1
+#[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Distance(pub i32);
+
+impl fmt::Display for Steps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,942 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::render::bounds;
+use crate::{canonical_runs, coalesce, cost_for_segment, distance, fnv1a, intersections_where, intersects, intersects_along,
+            key_range, normalize, path_to_segments, split_on_direction, steps_to, vertices, wire_start, Axis, FNV_OFFSET,
+            OverlapPoints, PathSegment, Point, PointWithCost, Rect, Segment};
+
+// counter-clockwise order, so the next heading is a left turn
+const HEADINGS: [char; 4] = ['R', 'U', 'L', 'D'];
+
+// how each move turns relative to the one before it
+#[derive(Copy, Debug, Clone, PartialEq, Default)]
+pub struct TurnStats {
+    pub left: usize,
+    pub right: usize,
+    pub straight: usize,
+    // moves going straight back the way the previous one came
+    pub reversals: usize,
+}
+
+fn heading(direction: char) -> usize {
+    HEADINGS.iter().position(|h| *h == direction).expect("unknown direction")
+}
+
+// counter-clockwise quarter turns from one heading to the next: 0 straight, 1 left, 2 back, 3 right
+fn quarter_turns(from: char, to: char) -> usize {
+    (heading(to) + 4 - heading(from)) % 4
+}
+
+pub fn opposite(direction: char) -> char {
+    HEADINGS[(heading(direction) + 2) % 4]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SliceError {
+    Reversed { from: i32, to: i32 },
+    // step is outside 0..=length
+    OutOfRange { step: i32, length: i64 },
+}
+
+impl fmt::Display for SliceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SliceError::Reversed { from, to } => write!(f, "slice from step {} ends before it starts, at {}", from, to),
+            SliceError::OutOfRange { step, length } => write!(f, "step {} is outside the wire, which is {} long", step, length),
+        }
+    }
+}
+
+impl std::error::Error for SliceError {}
+
+// one wire as parsed, together with its normalized segments
+#[derive(Debug, Clone)]
+pub struct Wire {
+    pub moves: Vec<PathSegment>,
+    pub origin: Point,
+    pub segments: Vec<Segment>,
+}
+
+impl Wire {
+    pub fn new(moves: Vec<PathSegment>, origin: Point) -> Wire {
+        let segments = normalize(path_to_segments(moves.clone(), origin));
+        Wire { moves, origin, segments }
+    }
+
+    // tail's moves walked on from where this wire ends; tail's own origin is not used
+    pub fn concat(&self, tail: &Wire) -> Wire {
+        let mut joined = self.clone();
+        joined.extend_moves(tail.moves.iter().copied());
+        joined
+    }
+
+    pub fn extend_moves(&mut self, moves: impl IntoIterator<Item=PathSegment>) {
+        self.moves.extend(moves);
+        self.segments = normalize(path_to_segments(self.moves.clone(), self.origin));
+    }
+
+    // consecutive moves in the same direction merged and empty ones dropped, which changes neither
+    // answer; the move level counterpart of merged_segments
+    pub fn coalesced(&self) -> Wire {
+        let mut merged: Vec<PathSegment> = vec!();
+        for m in self.moves.iter().filter(|m| m.distance != 0) {
+            match merged.last_mut() {
+                Some(last) if last.direction == m.direction => last.distance += m.distance,
+                _ => merged.push(*m),
+            }
+        }
+        Wire::new(merged, self.origin)
+    }
+
+    // FNV-1a over the coalesced moves as direction letter and little endian distance, stable across
+    // platforms; unlike fingerprint() of the segments it depends on the walking order. The origin is
+    // not part of it
+    pub fn fingerprint(&self) -> u64 {
+        self.coalesced().moves.iter().fold(FNV_OFFSET, |hash, m| {
+            fnv1a(fnv1a(hash, &[m.direction as u8]), &m.distance.to_le_bytes())
+        })
+    }
+
+    // whether both walk the same corners in the same order, however the moves are chunked
+    pub fn same_geometry(&self, other: &Wire) -> bool {
+        let corners = |w: &Wire| match vertices(&w.merged_segments()) {
+            v if v.is_empty() => vec![w.origin],
+            v => v,
+        };
+        corners(self) == corners(other)
+    }
+
+    // same_geometry, but a wire also matches the same lines walked in another order or direction
+    pub fn same_geometry_ignoring_order(&self, other: &Wire) -> bool {
+        let runs = |w: &Wire| match canonical_runs(&w.segments) {
+            r if r.is_empty() => vec![(w.origin, w.origin)],
+            r => r,
+        };
+        runs(self) == runs(other)
+    }
+
+    // cancels moves that double straight back (R5,L2 becomes R3, R5,L5 nothing) until none are
+    // left. Never applied implicitly: both the steps and the tips of the removed spurs are real
+    // wire, so part 2 and, when a crossing sits on such a tip, part 1 can change
+    pub fn simplified(&self) -> Wire {
+        let mut kept: Vec<PathSegment> = vec!();
+        for m in self.moves.iter().filter(|m| m.distance != 0) {
+            let mut rest = *m;
+            while let Some(top) = kept.last_mut() {
+                if top.direction != opposite(rest.direction) {
+                    break;
+                }
+                if top.distance > rest.distance {
+                    top.distance -= rest.distance;
+                    rest.distance = 0;
+                    break;
+                }
+                rest.distance -= top.distance;
+                kept.pop();
+                if rest.distance == 0 {
+                    break;
+                }
+            }
+            if rest.distance != 0 {
+                kept.push(rest);
+            }
+        }
+        Wire::new(kept, self.origin)
+    }
+
+    // the transforms below act on the plane around (0,0), so applying the same one to both wires
+    // keeps their shared origin shared
+
+    pub fn translated(&self, dx: i32, dy: i32) -> Wire {
+        Wire::new(self.moves.clone(), Point::new(self.origin.x() + dx, self.origin.y() + dy))
+    }
+
+    // k quarter turns counter-clockwise, so U becomes L, then D, then R
+    pub fn rotated90(&self, k: i32) -> Wire {
+        let k = k.rem_euclid(4);
+        let mut origin = self.origin;
+        for _ in 0..k {
+            origin = Point::new(-origin.y(), origin.x());
+        }
+        self.map_moves(|d| HEADINGS[(heading(d) + k as usize) % 4], origin)
+    }
+
+    // x negated, so L and R swap
+    pub fn mirrored_x(&self) -> Wire {
+        let swap = |d| match d { 'L' => 'R', 'R' => 'L', d => d };
+        self.map_moves(swap, Point::new(-self.origin.x(), self.origin.y()))
+    }
+
+    // y negated, so U and D swap
+    pub fn mirrored_y(&self) -> Wire {
+        let swap = |d| match d { 'U' => 'D', 'D' => 'U', d => d };
+        self.map_moves(swap, Point::new(self.origin.x(), -self.origin.y()))
+    }
+
+    fn map_moves(&self, direction: impl Fn(char) -> char, origin: Point) -> Wire {
+        let moves = self.moves.iter().map(|m| PathSegment { direction: direction(m.direction), distance: m.distance }).collect();
+        Wire::new(moves, origin)
+    }
+
+    // segments with consecutive moves in the same direction merged into one
+    pub fn merged_segments(&self) -> Vec<Segment> {
+        normalize(coalesce(&path_to_segments(self.moves.clone(), self.origin)))
+    }
+
+    // sum of the move distances, i.e. the steps to walk the whole wire
+    pub fn total_length(&self) -> i64 {
+        self.moves.iter().map(|m| m.distance as i64).sum()
+    }
+
+    // for a wire ending back at its origin, the area of the loop by the shoelace formula over its
+    // corners, None otherwise. The formula sums signed areas, so parts walked in opposite directions
+    // cancel: a figure eight of two equal lobes encloses 0
+    pub fn enclosed_area(&self) -> Option<i64> {
+        if !self.is_closed() {
+            return None;
+        }
+        let corners = vertices(&self.segments);
+        let twice: i64 = corners.windows(2)
+            .map(|w| w[0].x() as i64 * w[1].y() as i64 - w[1].x() as i64 * w[0].y() as i64)
+            .sum();
+        Some(twice.abs() / 2)
+    }
+
+    // the lattice points inside the loop or on it, by Pick's theorem with the wire length as the
+    // boundary count; only exact for a loop that never touches itself
+    pub fn enclosed_area_including_boundary(&self) -> Option<i64> {
+        let area = self.enclosed_area()?;
+        Some(area + self.total_length() / 2 + 1)
+    }
+
+    // the convex hull of the origin and every corner, counter-clockwise from the lowest x then y,
+    // by Andrew's monotone chain. Corners along a hull edge are dropped, so a straight wire gives
+    // its two ends
+    pub fn convex_hull(&self) -> Vec<Point> {
+        let mut points = vertices(&self.segments);
+        points.push(self.origin);
+        points.sort_unstable_by_key(|p| (p.x(), p.y()));
+        points.dedup();
+        if points.len() < 3 {
+            return points;
+        }
+        let cross = |o: Point, a: Point, b: Point| {
+            (a.x() - o.x()) as i64 * (b.y() - o.y()) as i64 - (a.y() - o.y()) as i64 * (b.x() - o.x()) as i64
+        };
+        let mut hull: Vec<Point> = vec!();
+        for pass in 0..2 {
+            let floor = hull.len();
+            let chain: Vec<Point> = if pass == 0 { points.clone() } else { points.iter().rev().copied().collect() };
+            for p in chain {
+                while hull.len() >= floor + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0 {
+                    hull.pop();
+                }
+                hull.push(p);
+            }
+            // the last point starts the other chain
+            hull.pop();
+        }
+        hull
+    }
+
+    // the area inside convex_hull
+    pub fn hull_area(&self) -> f64 {
+        let hull = self.convex_hull();
+        let twice: i64 = (0..hull.len())
+            .map(|n| (hull[n], hull[(n + 1) % hull.len()]))
+            .map(|(a, b)| a.x() as i64 * b.y() as i64 - b.x() as i64 * a.y() as i64)
+            .sum();
+        twice.abs() as f64 / 2.0
+    }
+
+    // closed rectangles walked as four consecutive coalesced moves turning the same way each time,
+    // either way round and starting on any side, with the index of their first move in
+    // coalesced().moves. A match takes its four moves, the next search starts after them
+    pub fn find_rectangles(&self) -> Vec<(Rect, usize)> {
+        let wire = self.coalesced();
+        let corners = vertices(&wire.segments);
+        let moves = &wire.moves;
+        let turn = |k: usize| (heading(moves[k + 1].direction) + 4 - heading(moves[k].direction)) % 4;
+        let mut found: Vec<(Rect, usize)> = vec!();
+        let mut i = 0;
+        while i + 4 <= moves.len() {
+            let turns = [turn(i), turn(i + 1), turn(i + 2)];
+            let closes = moves[i].distance == moves[i + 2].distance && moves[i + 1].distance == moves[i + 3].distance;
+            if closes && (turns == [1, 1, 1] || turns == [3, 3, 3]) {
+                let (a, b) = (corners[i], corners[i + 2]);
+                let rect = Rect { min: Point::new(a.x().min(b.x()), a.y().min(b.y())), max: Point::new(a.x().max(b.x()), a.y().max(b.y())) };
+                found.push((rect, i));
+                i += 4;
+            } else {
+                i += 1;
+            }
+        }
+        found
+    }
+
+    // zero-length moves have no heading and are skipped
+    pub fn turns(&self) -> TurnStats {
+        let mut stats = TurnStats::default();
+        let headings: Vec<char> = self.moves.iter().filter(|m| m.distance != 0).map(|m| m.direction).collect();
+        for pair in headings.windows(2) {
+            match quarter_turns(pair[0], pair[1]) {
+                0 => stats.straight += 1,
+                1 => stats.left += 1,
+                2 => stats.reversals += 1,
+                _ => stats.right += 1,
+            }
+        }
+        stats
+    }
+
+    // net quarter turns, left +1 and right -1, reversals counting neither way. A closed wire also
+    // turns from its last move back into its first, so a loop walked once winds +4 or -4
+    pub fn winding(&self) -> i32 {
+        let headings: Vec<char> = self.moves.iter().filter(|m| m.distance != 0).map(|m| m.direction).collect();
+        let mut pairs: Vec<(char, char)> = headings.windows(2).map(|w| (w[0], w[1])).collect();
+        if let (Some(first), Some(last), true) = (headings.first(), headings.last(), self.is_closed()) {
+            pairs.push((*last, *first));
+        }
+        pairs.iter().map(|(from, to)| match quarter_turns(*from, *to) {
+            1 => 1,
+            3 => -1,
+            _ => 0,
+        }).sum()
+    }
+
+    // ends where it starts, having gone somewhere
+    pub fn is_closed(&self) -> bool {
+        vertices(&self.segments).last() == Some(&self.origin)
+    }
+
+    // a closed wire that winds once and meets itself only at its origin
+    pub fn is_simple_loop(&self) -> bool {
+        self.is_closed() && self.winding().abs() == 4 && self.self_crossings() == 1
+    }
+
+    // a wire without moves is just its origin
+    pub fn bounding_box(&self) -> Rect {
+        if self.segments.is_empty() {
+            return Rect { min: self.origin, max: self.origin };
+        }
+        bounds(&[&self.segments])
+    }
+
+    // steps on the first visit to p, the fewest over every segment through it, or None off the wire
+    pub fn steps_to(&self, p: Point) -> Option<i32> {
+        if p == self.origin {
+            return Some(0);
+        }
+        steps_to(&self.segments, p)
+    }
+
+    // where the wire is after exactly s steps, None past its end
+    pub fn point_at_step(&self, s: i32) -> Option<Point> {
+        if s == 0 {
+            return Some(self.origin);
+        }
+        let segment = self.segments.iter().find(|seg| seg.steps < s && s <= seg.steps + distance(seg.end1, seg.end2))?;
+        // walk from the end the wire entered by, not the normalized end1
+        let (start, end) = (segment.start, segment.end());
+        let walked = s - segment.steps;
+        Some(Point::new(start.x() + (end.x() - start.x()).signum() * walked, start.y() + (end.y() - start.y()).signum() * walked))
+    }
+
+    // the stretch walked between steps from and to, as a wire of its own starting where the cut is
+    pub fn slice_steps(&self, from: i32, to: i32) -> Result<Wire, SliceError> {
+        if from > to {
+            return Err(SliceError::Reversed { from, to });
+        }
+        let length = self.total_length();
+        if let Some(step) = [from, to].iter().copied().find(|s| *s < 0 || *s as i64 > length) {
+            return Err(SliceError::OutOfRange { step, length });
+        }
+        let mut moves: Vec<PathSegment> = vec!();
+        let mut walked = 0;
+        for m in &self.moves {
+            let (start, end) = (walked, walked + m.distance);
+            walked = end;
+            let kept = end.min(to) - start.max(from);
+            if kept > 0 {
+                moves.push(PathSegment { direction: m.direction, distance: kept });
+            }
+        }
+        let origin = self.point_at_step(from).expect("from is within the wire");
+        Ok(Wire::new(moves, origin))
+    }
+
+    // the same path walked back from its far end, so steps count from there
+    pub fn reversed(&self) -> Wire {
+        let end = vertices(&self.segments).last().copied().unwrap_or(self.origin);
+        let moves = self.moves.iter().rev().map(|m| PathSegment { direction: opposite(m.direction), distance: m.distance }).collect();
+        Wire::new(moves, end)
+    }
+
+    // the point of the wire nearest q, its distance from q and the steps to reach it; ties go to
+    // fewer steps
+    pub fn nearest_point(&self, q: Point) -> (Point, i64, i32) {
+        let mut best = (self.origin, distance(self.origin, q) as i64, 0);
+        for s in &self.segments {
+            // normalized, so end1 holds the low coordinates
+            let p = Point::new(q.x().max(s.end1.x()).min(s.end2.x()), q.y().max(s.end1.y()).min(s.end2.y()));
+            let candidate = (p, distance(p, q) as i64, cost_for_segment(p, s));
+            if (candidate.1, candidate.2) < (best.1, best.2) {
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    pub fn count_moves(&self, direction: char) -> usize {
+        self.moves.iter().filter(|m| m.direction == direction).count()
+    }
+
+    pub fn longest_move(&self) -> Option<i32> {
+        self.moves.iter().map(|m| m.distance).max()
+    }
+
+    pub fn shortest_move(&self) -> Option<i32> {
+        self.moves.iter().map(|m| m.distance).min()
+    }
+
+    // distinct points where the wire meets itself, apart from the corners joining consecutive segments
+    pub fn self_crossings(&self) -> usize {
+        let segments = self.merged_segments();
+        let mut points: HashSet<Point> = HashSet::new();
+        let mut found = vec!();
+        for i in 0..segments.len().saturating_sub(2) {
+            let (horizontals, verticals) = split_on_direction(segments[i + 2..].to_vec());
+            found.clear();
+            intersects(segments[i], &horizontals, &verticals, &mut found);
+            points.extend(found.iter().map(|f| f.point));
+        }
+        points.len()
+    }
+}
+
+// a wire's segments by direction, sorted by position, for answering many range queries cheaply
+#[derive(Debug, Clone)]
+pub struct PreparedWire {
+    pub start: Point,
+    // by y
+    horizontals: Vec<Segment>,
+    // by x
+    verticals: Vec<Segment>,
+}
+
+impl PreparedWire {
+    // segments must be normalized
+    pub fn new(segments: &[Segment]) -> PreparedWire {
+        let (mut horizontals, mut verticals) = split_on_direction(segments.to_vec());
+        horizontals.sort_unstable_by_key(|s| s.end1.y());
+        verticals.sort_unstable_by_key(|s| s.end1.x());
+        PreparedWire { start: wire_start(segments), horizontals, verticals }
+    }
+
+    // the segments reaching into rect
+    fn in_rect(&self, rect: Rect) -> Vec<Segment> {
+        let horizontals = key_range(&self.horizontals, |s| s.end1.y(), rect.min.y(), rect.max.y()).iter()
+            .filter(|s| s.end1.x() <= rect.max.x() && s.end2.x() >= rect.min.x());
+        let verticals = key_range(&self.verticals, |s| s.end1.x(), rect.min.x(), rect.max.x()).iter()
+            .filter(|s| s.end1.y() <= rect.max.y() && s.end2.y() >= rect.min.y());
+        horizontals.chain(verticals).copied().collect()
+    }
+
+    fn axis_mut(&mut self, s: &Segment) -> (&mut Vec<Segment>, i32) {
+        match Axis::of(s) {
+            Axis::Horizontal => (&mut self.horizontals, s.end1.y()),
+            Axis::Vertical => (&mut self.verticals, s.end1.x()),
+        }
+    }
+
+    // s must be normalized
+    pub fn insert(&mut self, s: Segment) {
+        let (sorted, key) = self.axis_mut(&s);
+        let at = sorted.partition_point(|t| Axis::of(t).across(t.end1) <= key);
+        sorted.insert(at, s);
+    }
+
+    // removes the segment walked from step s.steps; false if the wire has none
+    pub fn remove(&mut self, s: &Segment) -> bool {
+        let (sorted, _) = self.axis_mut(s);
+        match sorted.iter().position(|t| t.steps == s.steps && t.end1 == s.end1 && t.end2 == s.end2) {
+            Some(at) => {
+                sorted.remove(at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // the intersections of one segment of the other wire with this one, every point of an overlap
+    // included as solve counts them, each with the steps at which this wire's segment starts. Only
+    // the segments on its line and the ones crossing its span are looked at
+    pub fn intersections_with(&self, segment: Segment) -> Vec<(i32, PointWithCost)> {
+        let axis = Axis::of(&segment);
+        let (parallel, perpendicular) = match axis {
+            Axis::Horizontal => (&self.horizontals, &self.verticals),
+            Axis::Vertical => (&self.verticals, &self.horizontals),
+        };
+        let across = axis.across(segment.end1);
+        let on_line = key_range(parallel, |s| axis.across(s.end1), across, across);
+        let in_span = key_range(perpendicular, |s| axis.along(s.end1), axis.along(segment.end1), axis.along(segment.end2));
+        let mut pairs: Vec<(i32, PointWithCost)> = vec!();
+        let mut found: Vec<PointWithCost> = vec!();
+        let candidates = on_line.iter().map(|s| (s, true)).chain(in_span.iter().map(|s| (s, false)));
+        for (other, is_parallel) in candidates {
+            found.clear();
+            let one = std::slice::from_ref(other);
+            if is_parallel {
+                intersects_along(axis, segment, one, &[], OverlapPoints::Every, &mut found);
+            } else {
+                intersects_along(axis, segment, &[], one, OverlapPoints::Ends, &mut found);
+            }
+            pairs.extend(found.iter().map(|f| (other.steps, *f)));
+        }
+        pairs
+    }
+
+    // as intersections_in_rect, with this wire as the first one
+    pub fn intersections_in_rect(&self, other: &PreparedWire, reference: Point, rect: Rect) -> Vec<PointWithCost> {
+        let exclude = Some(self.start).filter(|s| *s == other.start);
+        intersections_where(&self.in_rect(rect), &other.in_rect(rect), reference, exclude, |_| true, |p| rect.contains(p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all_intersections, solve};
+    use crate::random::{random_wire, Rng};
+    use crate::tests::{moves, EXAMPLES};
+
+    fn wire(line: &str) -> Wire {
+        Wire::new(moves(line), Point::ORIGIN)
+    }
+
+    #[test]
+    fn move_counts_and_extremes() {
+        let w = wire("R8,U5,L5,D3,R1,R1");
+        let counts: Vec<usize> = "LRUD".chars().map(|d| w.count_moves(d)).collect();
+        assert_eq!(counts, vec!(1, 3, 1, 1));
+        assert_eq!(w.longest_move(), Some(8));
+        assert_eq!(w.shortest_move(), Some(1));
+        // the last two moves merge into one segment
+        assert_eq!(w.merged_segments().len(), 5);
+        let empty = Wire::new(vec!(), Point::ORIGIN);
+        assert_eq!(empty.count_moves('R'), 0);
+        assert_eq!(empty.longest_move(), None);
+        assert_eq!(empty.shortest_move(), None);
+        assert!(empty.merged_segments().is_empty());
+    }
+
+    #[test]
+    fn self_crossings_skip_the_corners() {
+        assert_eq!(wire("R8,U5,L5,D3").self_crossings(), 0);
+        // the last move cuts back through the first at (3,0)
+        assert_eq!(wire("R8,U5,L5,D8").self_crossings(), 1);
+        // both lobes meet at the origin
+        assert_eq!(wire(FIGURE_EIGHT).self_crossings(), 1);
+        assert_eq!(wire("R5").self_crossings(), 0);
+    }
+
+    #[test]
+    fn steps_to_first_visits() {
+        let w = wire("R8,U5,L5,D3");
+        // mid segment, a corner on two segments, the origin, off the wire
+        assert_eq!(w.steps_to(Point::new(4, 0)), Some(4));
+        assert_eq!(w.steps_to(Point::new(8, 0)), Some(8));
+        assert_eq!(w.steps_to(Point::ORIGIN), Some(0));
+        assert_eq!(w.steps_to(Point::new(1, 1)), None);
+        // crosses (2,0) after 2 steps and again after 10
+        let revisits = wire("R4,U2,L2,D4");
+        assert_eq!(revisits.steps_to(Point::new(2, 0)), Some(2));
+        // a loop back to its origin is still there after 0 steps
+        assert_eq!(wire("R2,U2,L2,D2").steps_to(Point::ORIGIN), Some(0));
+        assert_eq!(Wire::new(moves("U3"), Point::new(5, 5)).steps_to(Point::new(5, 7)), Some(2));
+    }
+
+    #[test]
+    fn nearest_point_clamps_onto_segments() {
+        let w = wire("R8,U5,L5,D3");
+        // on the wire, at no distance
+        assert_eq!(w.nearest_point(Point::new(8, 2)), (Point::new(8, 2), 0, 10));
+        assert_eq!(w.nearest_point(Point::new(3, 4)), (Point::new(3, 4), 0, 19));
+        // beyond either end of the wire
+        assert_eq!(w.nearest_point(Point::new(-4, 1)), (Point::ORIGIN, 5, 0));
+        assert_eq!(wire("R8,U5").nearest_point(Point::new(8, 9)), (Point::new(8, 5), 4, 13));
+        // 1 from (3,0) and from the end at (3,2), the fewer steps win
+        assert_eq!(w.nearest_point(Point::new(3, 1)), (Point::new(3, 0), 1, 3));
+    }
+
+    #[test]
+    fn point_at_step_walks_the_true_direction() {
+        // L and D moves are the ones normalize swaps
+        let w = wire("R8,U5,L5,D3");
+        assert_eq!(w.point_at_step(0), Some(Point::ORIGIN));
+        assert_eq!(w.point_at_step(8), Some(Point::new(8, 0)));
+        assert_eq!(w.point_at_step(15), Some(Point::new(6, 5)));
+        assert_eq!(w.point_at_step(20), Some(Point::new(3, 3)));
+        assert_eq!(w.point_at_step(21), Some(Point::new(3, 2)));
+        assert_eq!(w.point_at_step(22), None);
+    }
+
+    #[test]
+    fn point_at_step_finds_the_part_2_crossing() {
+        for (line1, line2, _, steps) in EXAMPLES.iter() {
+            let (wire1, wire2) = (wire(line1), wire(line2));
+            let solution = solve(wire1.segments.clone(), wire2.segments.clone(), Point::ORIGIN).unwrap();
+            let (steps1, steps2) = (wire1.steps_to(solution.steps_at).unwrap(), wire2.steps_to(solution.steps_at).unwrap());
+            assert_eq!(steps1 + steps2, *steps);
+            assert_eq!(wire1.point_at_step(steps1), Some(solution.steps_at));
+            assert_eq!(wire2.point_at_step(steps2), Some(solution.steps_at));
+        }
+    }
+
+    #[test]
+    fn slice_steps_cuts_moves() {
+        let w = wire("R8,U5,L5,D3");
+        let slice = w.slice_steps(4, 15).unwrap();
+        assert_eq!(slice.origin, Point::new(4, 0));
+        assert_eq!(slice.moves, moves("R4,U5,L2"));
+        assert_eq!(slice.segments[0].steps, 0);
+        assert_eq!(w.slice_steps(0, 21).unwrap().moves, w.moves);
+        assert_eq!(w.slice_steps(3, 3).unwrap().moves, vec!());
+        assert_eq!(w.slice_steps(5, 4).unwrap_err(), SliceError::Reversed { from: 5, to: 4 });
+        assert_eq!(w.slice_steps(-1, 4).unwrap_err(), SliceError::OutOfRange { step: -1, length: 21 });
+        assert_eq!(w.slice_steps(4, 22).unwrap_err(), SliceError::OutOfRange { step: 22, length: 21 });
+    }
+
+    #[test]
+    fn a_slice_keeps_the_crossings_in_its_range() {
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            let (wire1, wire2) = (wire(line1), wire(line2));
+            for i in all_intersections(&wire1.segments, &wire2.segments).iter().filter(|i| i.point != Point::ORIGIN) {
+                let steps = wire1.steps_to(i.point).unwrap();
+                let slice = wire1.slice_steps(steps - 1, steps + 1).unwrap();
+                assert!(all_intersections(&slice.segments, &wire2.segments).iter().any(|s| s.point == i.point), "{}", i.point);
+            }
+        }
+    }
+
+    #[test]
+    fn reversing_twice_round_trips() {
+        let w = wire("R8,U5,L5,D3");
+        let reversed = w.reversed();
+        assert_eq!(reversed.origin, Point::new(3, 2));
+        assert_eq!(reversed.moves, moves("U3,R5,D5,L8"));
+        assert_eq!(reversed.total_length(), w.total_length());
+        assert_eq!(reversed.reversed().moves, w.moves);
+        assert_eq!(reversed.reversed().origin, w.origin);
+        // same lines, so the same crossings, but walked from the other end
+        let other = wire("U7,R6,D4,L4");
+        let points = |a: &Wire, b: &Wire| {
+            let mut found: Vec<(i32, i32)> = all_intersections(&a.segments, &b.segments).iter().map(|i| (i.point.x(), i.point.y())).collect();
+            found.sort();
+            found.dedup();
+            found
+        };
+        assert_eq!(points(&reversed, &other), points(&w, &other));
+    }
+
+    #[test]
+    fn concat_equals_the_joined_moves() {
+        for (line1, line2, _, _) in EXAMPLES.iter() {
+            // split wire 1 after each of its moves and glue it back
+            let tokens: Vec<&str> = line1.split(',').collect();
+            let other = wire(line2);
+            for at in 1..tokens.len() {
+                let (head, tail) = (wire(&tokens[..at].join(",")), wire(&tokens[at..].join(",")));
+                let joined = head.concat(&tail);
+                assert_eq!(joined.moves, wire(line1).moves);
+                assert_eq!(solve(joined.segments.clone(), other.segments.clone(), Point::ORIGIN),
+                           solve(wire(line1).segments, other.segments.clone(), Point::ORIGIN));
+                let mut extended = head.clone();
+                extended.extend_moves(tail.moves.iter().copied());
+                assert_eq!(extended.segments.len(), joined.segments.len());
+                assert_eq!(solve(extended.segments, other.segments.clone(), Point::ORIGIN),
+                           solve(joined.segments, other.segments.clone(), Point::ORIGIN));
+            }
+        }
+    }
+
+    #[test]
+    fn concat_lengths_add_and_steps_run_on() {
+        let (head, tail) = (wire("R8,U5,L5,D3"), Wire::new(moves("U7,R6,D4,L4"), Point::new(50, 50)));
+        let joined = head.concat(&tail);
+        assert_eq!(joined.total_length(), head.total_length() + tail.total_length());
+        // the tail starts where the head ends, 21 steps in, wherever its own origin was
+        let join = &joined.segments[head.segments.len()];
+        assert_eq!((join.start, join.steps), (Point::new(3, 2), 21));
+        assert_eq!(joined.point_at_step(28), Some(Point::new(3, 9)));
+        for pair in joined.segments.windows(2) {
+            assert_eq!(pair[0].end(), pair[1].start);
+            assert_eq!(pair[0].steps + pair[0].length, pair[1].steps);
+        }
+    }
+
+    fn ends(w: &Wire) -> Vec<(Point, Point)> {
+        w.segments.iter().map(|s| (s.end1, s.end2)).collect()
+    }
+
+    #[test]
+    fn transforms_move_every_segment() {
+        let p = Point::new;
+        let w = Wire::new(moves("R2,U1"), p(1, 0));
+        assert_eq!(ends(&w.translated(2, -1)), vec!((p(3, -1), p(5, -1)), (p(5, -1), p(5, 0))));
+        assert_eq!(ends(&w.rotated90(1)), vec!((p(0, 1), p(0, 3)), (p(-1, 3), p(0, 3))));
+        assert_eq!(ends(&w.rotated90(2)), vec!((p(-3, 0), p(-1, 0)), (p(-3, -1), p(-3, 0))));
+        assert_eq!(ends(&w.rotated90(-1)), vec!((p(0, -3), p(0, -1)), (p(0, -3), p(1, -3))));
+        assert_eq!(ends(&w.rotated90(4)), ends(&w));
+        assert_eq!(ends(&w.mirrored_x()), vec!((p(-3, 0), p(-1, 0)), (p(-3, 0), p(-3, 1))));
+        assert_eq!(ends(&w.mirrored_y()), vec!((p(1, 0), p(3, 0)), (p(3, -1), p(3, 0))));
+        // the walk is kept, not just the lines
+        assert_eq!(w.rotated90(1).point_at_step(3), Some(p(-1, 3)));
+        assert_eq!(w.mirrored_x().point_at_step(1), Some(p(-2, 0)));
+    }
+
+    #[test]
+    fn the_same_transform_of_both_wires_keeps_the_answers() {
+        let transforms: [fn(&Wire) -> Wire; 5] = [
+            |w| w.rotated90(1),
+            |w| w.rotated90(3),
+            |w| w.mirrored_x(),
+            |w| w.mirrored_y(),
+            |w| w.translated(-40, 7),
+        ];
+        for (line1, line2, distance, steps) in EXAMPLES.iter() {
+            for transform in &transforms {
+                let (wire1, wire2) = (transform(&wire(line1)), transform(&wire(line2)));
+                let solution = solve(wire1.segments, wire2.segments, wire1.origin).unwrap();
+                assert_eq!((solution.distance.0, solution.steps.0), (*distance, *steps));
+            }
+        }
+    }
+
+    #[test]
+    fn simplified_cancels_reversals() {
+        assert_eq!(wire("R5,L2").simplified().moves, moves("R3"));
+        assert_eq!(wire("R5,L5").simplified().moves, vec!());
+        // L3 and L2 use up R5 between them, then R1 is all that is left
+        assert_eq!(wire("R5,L3,L2,R1").simplified().moves, moves("R1"));
+        assert_eq!(wire("U2,R5,L7,R2,D2").simplified().moves, vec!());
+        assert_eq!(wire("R3,U0,L1").simplified().moves, moves("R2"));
+    }
+
+    #[test]
+    fn simplified_keeps_part_1_but_not_part_2() {
+        // the first example's wire 1 with a 4 step spur it walks back along
+        let (detour, other) = (wire("R4,L2,R2,R4,U5,L5,D3"), wire("U7,R6,D4,L4"));
+        let simplified = detour.simplified();
+        assert_eq!(simplified.coalesced().moves, moves("R8,U5,L5,D3"));
+        let before = solve(detour.segments, other.segments.clone(), Point::ORIGIN).unwrap();
+        let after = solve(simplified.segments, other.segments, Point::ORIGIN).unwrap();
+        assert_eq!((before.distance.0, before.steps.0), (6, 34));
+        assert_eq!((after.distance.0, after.steps.0), (6, 30));
+    }
+
+    // each move cut into up to three moves the same way, with an empty one now and then
+    fn chopped(w: &Wire, rng: &mut Rng) -> Wire {
+        let mut pieces: Vec<PathSegment> = vec!();
+        for m in &w.moves {
+            let cut = rng.below(m.distance as u64 + 1) as i32;
+            let second = rng.below((m.distance - cut) as u64 + 1) as i32;
+            for distance in [cut, second, m.distance - cut - second] {
+                pieces.push(PathSegment { direction: m.direction, distance });
+            }
+        }
+        Wire::new(pieces, w.origin)
+    }
+
+    #[test]
+    fn coalescing_keeps_both_answers() {
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let (wire1, wire2) = (Wire::new(random_wire(&mut rng, 30), Point::ORIGIN), Wire::new(random_wire(&mut rng, 30), Point::ORIGIN));
+            let (chopped1, chopped2) = (chopped(&wire1, &mut rng), chopped(&wire2, &mut rng));
+            assert_eq!(chopped1.coalesced().moves, wire1.coalesced().moves);
+            let before = solve(chopped1.segments.clone(), chopped2.segments.clone(), Point::ORIGIN);
+            let after = solve(chopped1.coalesced().segments, chopped2.coalesced().segments, Point::ORIGIN);
+            assert_eq!(before, after);
+            assert_eq!(before, solve(wire1.segments, wire2.segments, Point::ORIGIN));
+        }
+    }
+
+    #[test]
+    fn coalescing_single_steps() {
+        let w = wire(&["R1"; 12].join(","));
+        assert_eq!(w.segments.len(), 12);
+        let coalesced = w.coalesced();
+        assert_eq!(coalesced.moves, moves("R12"));
+        assert_eq!(coalesced.segments.len(), 1);
+        assert_eq!(coalesced.steps_to(Point::new(7, 0)), w.steps_to(Point::new(7, 0)));
+    }
+
+    #[test]
+    fn same_geometry_ignores_chunking_but_not_order() {
+        let (chunked, whole) = (wire("R3,R4,U1,U1"), wire("R7,U2"));
+        assert!(chunked.same_geometry(&whole));
+        assert!(chunked.same_geometry_ignoring_order(&whole));
+        // the same cells walked from the far end
+        let back = whole.reversed();
+        assert!(!whole.same_geometry(&back));
+        assert!(whole.same_geometry_ignoring_order(&back));
+        // one cell more
+        assert!(!whole.same_geometry(&wire("R7,U3")));
+        assert!(!whole.same_geometry_ignoring_order(&wire("R7,U3")));
+        // no moves at all against a wire of empty moves
+        assert!(Wire::new(vec!(), Point::ORIGIN).same_geometry(&wire("R0")));
+    }
+
+    #[test]
+    fn fingerprints_are_pinned() {
+        // a change here means every stored fingerprint changes too
+        let pinned = [
+            (0x56da_a063_0e1a_8735, 0xcf82_b570_0c13_bef9),
+            (0x9234_49bc_5cab_40d3, 0xda05_bec7_389c_2d31),
+            (0x69ab_74cd_9033_b89b, 0xf4e9_6b60_ec1c_adf9),
+        ];
+        for ((line1, line2, _, _), (fingerprint1, fingerprint2)) in EXAMPLES.iter().zip(pinned.iter()) {
+            assert_eq!((wire(line1).fingerprint(), wire(line2).fingerprint()), (*fingerprint1, *fingerprint2), "{} / {}", line1, line2);
+        }
+        // chunking, empty moves and the origin do not matter, the walking order does
+        let w = wire("R8,U5,L5,D3");
+        assert_eq!(wire("R3,R5,U5,L0,L5,D3").fingerprint(), w.fingerprint());
+        assert_eq!(w.translated(10, -3).fingerprint(), w.fingerprint());
+        assert_ne!(w.reversed().fingerprint(), w.fingerprint());
+    }
+
+    // two 2 by 2 lobes meeting at the origin, the first walked counter-clockwise, the second clockwise
+    const FIGURE_EIGHT: &str = "R2,U2,L2,D2,D2,L2,U2,R2";
+
+    #[test]
+    fn enclosed_area_of_loops() {
+        let square = wire("R10,U10,L10,D10");
+        assert_eq!(square.enclosed_area(), Some(100));
+        assert_eq!(square.enclosed_area_including_boundary(), Some(121));
+        // either way round
+        assert_eq!(square.reversed().enclosed_area(), Some(100));
+        // a 4 by 4 square missing its top left 2 by 2 corner
+        let l_shape = wire("R4,U2,L2,U2,L2,D4");
+        assert_eq!(l_shape.enclosed_area(), Some(12));
+        assert_eq!(l_shape.enclosed_area_including_boundary(), Some(21));
+        // the signed lobes cancel
+        assert_eq!(wire(FIGURE_EIGHT).enclosed_area(), Some(0));
+        assert_eq!(wire("R2,U2,L2,D2,L2,D2,R2,U2").enclosed_area(), Some(8));
+        assert_eq!(wire("R8,U5,L5,D3").enclosed_area(), None);
+        assert_eq!(wire("R8,U5,L5,D3").enclosed_area_including_boundary(), None);
+    }
+
+    #[test]
+    fn convex_hull_drops_points_along_its_edges() {
+        let p = Point::new;
+        let rectangle = wire("R4,U2,L4,D2");
+        assert_eq!(rectangle.convex_hull(), vec!(p(0, 0), p(4, 0), p(4, 2), p(0, 2)));
+        assert_eq!(rectangle.hull_area(), 8.0);
+        // the inner corners of the steps all lie on the line from the origin to the top
+        let staircase = wire("R1,U1,R1,U1,R1,U1");
+        assert_eq!(staircase.convex_hull(), vec!(p(0, 0), p(1, 0), p(3, 2), p(3, 3)));
+        assert_eq!(staircase.hull_area(), 2.5);
+        let straight = wire("R3,R2");
+        assert_eq!(straight.convex_hull(), vec!(p(0, 0), p(5, 0)));
+        assert_eq!(straight.hull_area(), 0.0);
+        assert_eq!(Wire::new(vec!(), p(2, 2)).convex_hull(), vec!(p(2, 2)));
+    }
+
+    fn rect(x1: i32, y1: i32, x2: i32, y2: i32) -> Rect {
+        Rect { min: Point::new(x1, y1), max: Point::new(x2, y2) }
+    }
+
+    #[test]
+    fn bounding_box_of_all_four_quadrants() {
+        let w = wire("R3,U2,L7,D6,R1");
+        assert_eq!(w.bounding_box(), rect(-4, -4, 3, 2));
+        for corner in &[Point::new(-4, -4), Point::new(3, 2), Point::new(-4, 2), Point::new(3, -4), Point::ORIGIN] {
+            assert!(w.bounding_box().contains(*corner), "{}", corner);
+        }
+        assert!(!w.bounding_box().contains(Point::new(4, 0)));
+        assert!(!w.bounding_box().contains(Point::new(0, -5)));
+        // no moves, only the origin
+        let empty = Wire::new(vec!(), Point::new(5, -2));
+        assert_eq!(empty.bounding_box(), rect(5, -2, 5, -2));
+    }
+
+    #[test]
+    fn union_of_disjoint_boxes() {
+        let (a, b) = (rect(-5, -5, -3, -1), rect(2, 4, 6, 9));
+        assert_eq!(a.union(&b), rect(-5, -5, 6, 9));
+        assert_eq!(b.union(&a), a.union(&b));
+        assert_eq!(a.union(&a), a);
+        // the gap between them is inside the union but in neither box
+        let gap = Point::new(0, 0);
+        assert!(a.union(&b).contains(gap) && !a.contains(gap) && !b.contains(gap));
+    }
+
+    #[test]
+    fn finds_closed_rectangles() {
+        let two = wire("R2,U2,L2,D2,R5,U1,R3,U3,L3,D3");
+        assert_eq!(two.find_rectangles(), vec!((rect(0, 0, 2, 2), 0), (rect(5, 1, 8, 4), 6)));
+        // a side too short or too long to close
+        assert_eq!(wire("R3,U2,L3,D1").find_rectangles(), vec!());
+        assert_eq!(wire("R3,U2,L2,D2").find_rectangles(), vec!());
+        // counter-clockwise, clockwise and starting on the top side
+        assert_eq!(wire("R3,U2,L3,D2").find_rectangles(), vec!((rect(0, 0, 3, 2), 0)));
+        assert_eq!(wire("U2,R3,D2,L3").find_rectangles(), vec!((rect(0, 0, 3, 2), 0)));
+        assert_eq!(wire("L3,D2,R3,U2").find_rectangles(), vec!((rect(-3, -2, 0, 0), 0)));
+        // matched on the coalesced moves, whose indices it reports
+        assert_eq!(wire("U1,R1,R2,U2,L3,D2").find_rectangles(), vec!((rect(0, 1, 3, 3), 1)));
+    }
+
+    #[test]
+    fn length_and_turns_of_hand_built_wires() {
+        assert_eq!(wire("R8,U5,L5,D3").total_length(), 21);
+        assert_eq!(Wire::new(vec!(), Point::ORIGIN).total_length(), 0);
+        assert_eq!(wire("R8,U5,L5,D3").turns(), TurnStats { left: 3, right: 0, straight: 0, reversals: 0 });
+        assert_eq!(wire("U7,R6,D4,L4").turns(), TurnStats { left: 0, right: 3, straight: 0, reversals: 0 });
+        // backtracks straight away, and walks 10 steps to end where it began
+        let back = wire("R5,L5");
+        assert_eq!(back.turns(), TurnStats { left: 0, right: 0, straight: 0, reversals: 1 });
+        assert_eq!(back.total_length(), 10);
+        assert_eq!(wire("R1,R2,R3").turns(), TurnStats { left: 0, right: 0, straight: 2, reversals: 0 });
+        assert_eq!(wire("R5").turns(), TurnStats::default());
+    }
+
+    #[test]
+    fn turn_table() {
+        // rows from, columns to, in the order R U L D: 0 straight, 1 left, 2 back, 3 right
+        let table = [
+            [0, 1, 2, 3],
+            [3, 0, 1, 2],
+            [2, 3, 0, 1],
+            [1, 2, 3, 0],
+        ];
+        for (from, row) in HEADINGS.iter().zip(table.iter()) {
+            for (to, turns) in HEADINGS.iter().zip(row.iter()) {
+                assert_eq!(quarter_turns(*from, *to), *turns, "{} to {}", from, to);
+            }
+        }
+        // R0 has no heading, so R to U is a left turn
+        let stats = wire("R1,U1,U1,R1,L1,D1,R1,R0,U1").turns();
+        assert_eq!(stats, TurnStats { left: 4, right: 1, straight: 1, reversals: 1 });
+    }
+
+    #[test]
+    fn winding_and_simple_loops() {
+        let square = wire("R4,U4,L4,D4");
+        assert_eq!(square.winding(), 4);
+        assert_eq!(square.reversed().winding(), -4);
+        assert!(square.is_simple_loop());
+        // one lobe each way round
+        let eight = wire(FIGURE_EIGHT);
+        assert!(eight.is_closed());
+        assert_eq!(eight.winding(), 0);
+        assert!(!eight.is_simple_loop());
+        let open = wire("R4,U4,L4");
+        assert_eq!(open.winding(), 2);
+        assert!(!open.is_closed());
+        assert!(!open.is_simple_loop());
+        // closed, but walked round twice
+        assert!(!wire("R4,U4,L4,D4,R4,U4,L4,D4").is_simple_loop());
+    }
+}